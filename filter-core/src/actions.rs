@@ -0,0 +1,723 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::api::{Entry, MinifluxClient, UpdateEntryRequest};
+use crate::compiled::{CompiledRuleSet, evaluate_rule_sets};
+use crate::rules::{Action, RuleMatch};
+use crate::webhook::{DEFAULT_RETRIES, WebhookDelivery, WebhookPayload};
+
+/// Remembers actions with side effects outside Miniflux that were already
+/// performed for an entry. Entries that are not marked read stay unread and
+/// match again on every poll cycle, so without this a webhook would fire
+/// every few minutes and an article would be saved over and over.
+///
+/// The history only lives in memory and is pruned to the unread entries
+/// after every poll, see [`ActionHistory::retain_entries`]. After a restart,
+/// matching entries that are still unread are sent to webhooks and saved
+/// once more.
+#[derive(Debug, Default)]
+pub struct ActionHistory {
+    performed: Mutex<HashSet<(u64, String)>>,
+}
+
+impl ActionHistory {
+    pub fn contains(&self, entry_id: u64, key: &str) -> bool {
+        self.performed
+            .lock()
+            .unwrap()
+            .contains(&(entry_id, key.to_string()))
+    }
+
+    pub fn record(&self, entry_id: u64, key: &str) {
+        self.performed
+            .lock()
+            .unwrap()
+            .insert((entry_id, key.to_string()));
+    }
+
+    /// Forget the entries not in `entry_ids`. Entries that were read or
+    /// removed never match again, so keeping them would only grow the history.
+    pub fn retain_entries(&self, entry_ids: &HashSet<u64>) {
+        self.performed
+            .lock()
+            .unwrap()
+            .retain(|(entry_id, _)| entry_ids.contains(entry_id));
+    }
+}
+
+/// Evaluate an entry against rule sets, fetching its original content
+/// first when a rule set has `fetch_content` set, or afterwards when a
+/// `fetchcontent` rule matched, in which case the entry is evaluated again
+/// against the full article. The content is fetched at most once per entry;
+/// Miniflux stores it, so later cycles already see the full article.
+pub async fn evaluate_with_full_content<'a>(
+    client: &MinifluxClient,
+    history: &ActionHistory,
+    rule_sets: &[&'a CompiledRuleSet],
+    entry: &mut Entry,
+) -> Vec<RuleMatch<'a>> {
+    if rule_sets.iter().any(|rs| rs.rule_set().fetch_content) {
+        fetch_full_content(client, history, entry).await;
+    }
+
+    let matches = evaluate_rule_sets(rule_sets, entry);
+
+    let wants_content = matches
+        .iter()
+        .any(|m| matches!(m.rule().action, Some(Action::FetchContent)));
+    if wants_content && fetch_full_content(client, history, entry).await {
+        return evaluate_rule_sets(rule_sets, entry);
+    }
+
+    matches
+}
+
+/// Replace the entry content with the original article, returning whether it
+/// was fetched. Failures are logged and the entry keeps its feed content.
+/// Failed attempts are recorded too, so a broken page is not fetched again
+/// on every poll cycle.
+async fn fetch_full_content(
+    client: &MinifluxClient,
+    history: &ActionHistory,
+    entry: &mut Entry,
+) -> bool {
+    if history.contains(entry.id, FETCH_CONTENT_KEY) {
+        return false;
+    }
+    history.record(entry.id, FETCH_CONTENT_KEY);
+
+    match client.fetch_original_content(entry.id).await {
+        Ok(content) => {
+            info!("Fetched original content for entry {}", entry.id);
+            entry.content = content;
+            true
+        }
+        Err(e) => {
+            warn!("{:#}", e);
+            false
+        }
+    }
+}
+
+/// Status change requested for an entry. When several rules match the same
+/// entry the greatest change wins, so removing beats marking as read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusChange {
+    Read,
+    Removed,
+}
+
+impl StatusChange {
+    /// The Miniflux entry status for this change
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusChange::Read => "read",
+            StatusChange::Removed => "removed",
+        }
+    }
+}
+
+/// The actions to take on a batch of entries, collected while evaluating
+/// them so each kind of action can be sent to Miniflux in one go
+#[derive(Debug, Default)]
+pub struct ActionPlan {
+    /// New status of each entry
+    pub status_changes: BTreeMap<u64, StatusChange>,
+    /// Entries to star, only containing entries that are not starred yet
+    pub bookmark: Vec<u64>,
+    /// Entries to send to the integrations configured in Miniflux
+    pub save: Vec<u64>,
+    /// New title and/or content of entries changed by rewrite rules
+    pub rewrites: BTreeMap<u64, UpdateEntryRequest>,
+    /// Webhook calls, at most one per entry and URL
+    pub webhooks: Vec<(u64, WebhookDelivery)>,
+    matched: usize,
+    kept: usize,
+    history: Option<Arc<ActionHistory>>,
+}
+
+impl ActionPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A plan that skips actions already recorded in the history
+    pub fn with_history(history: Arc<ActionHistory>) -> Self {
+        Self {
+            history: Some(history),
+            ..Self::default()
+        }
+    }
+
+    /// Record the actions of the rules an entry matched
+    pub fn add(&mut self, entry: &Entry, matches: &[RuleMatch]) {
+        if matches.is_empty() {
+            return;
+        }
+
+        self.matched += 1;
+
+        for rule_match in matches {
+            // Only scored rules have no action, and those never match
+            let Some(action) = &rule_match.rule().action else {
+                continue;
+            };
+            match action {
+                Action::MarkRead => self.change_status(entry.id, StatusChange::Read),
+                Action::Remove => self.change_status(entry.id, StatusChange::Removed),
+                Action::Bookmark => {
+                    // The Miniflux endpoint toggles, so starring an already
+                    // starred entry would un-star it
+                    if entry.starred {
+                        debug!("Entry {} is already starred", entry.id);
+                    } else {
+                        push_unique(&mut self.bookmark, entry.id);
+                    }
+                }
+                // Performed while evaluating, see `evaluate_with_full_content`
+                Action::FetchContent => {}
+                // Later rules were not evaluated, see `evaluate_rule_sets`
+                Action::Keep => self.kept += 1,
+                Action::Save => {
+                    if self.already_performed(entry.id, SAVE_KEY) {
+                        debug!("Entry {} was already saved", entry.id);
+                    } else {
+                        push_unique(&mut self.save, entry.id);
+                    }
+                }
+                Action::RewriteTitle { replacement, .. } => {
+                    let pending = self.rewrites.get(&entry.id).and_then(|u| u.title.clone());
+                    let current = pending.as_deref().unwrap_or(&entry.title);
+                    if let Some(title) = rewrite(rule_match, current, replacement)
+                        && title != entry.title
+                    {
+                        self.rewrites.entry(entry.id).or_default().title = Some(title);
+                    }
+                }
+                Action::RewriteContent { replacement, .. } => {
+                    let pending = self.rewrites.get(&entry.id).and_then(|u| u.content.clone());
+                    let current = pending.as_deref().unwrap_or(&entry.content);
+                    if let Some(content) = rewrite(rule_match, current, replacement)
+                        && content != entry.content
+                    {
+                        self.rewrites.entry(entry.id).or_default().content = Some(content);
+                    }
+                }
+                Action::Webhook {
+                    url,
+                    secret,
+                    retries,
+                } => {
+                    let planned = self
+                        .webhooks
+                        .iter()
+                        .any(|(id, delivery)| *id == entry.id && delivery.url == *url);
+                    if planned || self.already_performed(entry.id, &webhook_key(url)) {
+                        continue;
+                    }
+                    self.webhooks.push((
+                        entry.id,
+                        WebhookDelivery {
+                            url: url.clone(),
+                            secret: secret.clone(),
+                            retries: retries.unwrap_or(DEFAULT_RETRIES),
+                            payload: WebhookPayload::new(entry, rule_match),
+                        },
+                    ));
+                }
+            }
+        }
+    }
+
+    fn already_performed(&self, entry_id: u64, key: &str) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| history.contains(entry_id, key))
+    }
+
+    fn change_status(&mut self, entry_id: u64, change: StatusChange) {
+        let current = self.status_changes.entry(entry_id).or_insert(change);
+        *current = (*current).max(change);
+    }
+
+    /// Entries grouped by the status they should be set to
+    pub fn entries_by_status(&self) -> BTreeMap<StatusChange, Vec<u64>> {
+        let mut grouped: BTreeMap<StatusChange, Vec<u64>> = BTreeMap::new();
+        for (&entry_id, &change) in &self.status_changes {
+            grouped.entry(change).or_default().push(entry_id);
+        }
+        grouped
+    }
+
+    /// Number of entries that matched at least one rule
+    pub fn matched(&self) -> usize {
+        self.matched
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.status_changes.is_empty()
+            && self.bookmark.is_empty()
+            && self.save.is_empty()
+            && self.rewrites.is_empty()
+            && self.webhooks.is_empty()
+    }
+
+    /// Human readable summary of the planned actions
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        for (change, entry_ids) in self.entries_by_status() {
+            match change {
+                StatusChange::Read => parts.push(format!("marked {} as read", entry_ids.len())),
+                StatusChange::Removed => parts.push(format!("removed {}", entry_ids.len())),
+            }
+        }
+        if !self.bookmark.is_empty() {
+            parts.push(format!("bookmarked {}", self.bookmark.len()));
+        }
+        if !self.save.is_empty() {
+            parts.push(format!("saved {}", self.save.len()));
+        }
+        if !self.rewrites.is_empty() {
+            parts.push(format!("rewrote {}", self.rewrites.len()));
+        }
+        if !self.webhooks.is_empty() {
+            parts.push(format!("sent {} to webhooks", self.webhooks.len()));
+        }
+        if self.kept > 0 {
+            parts.push(format!("kept {}", self.kept));
+        }
+
+        if parts.is_empty() {
+            "no changes needed".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Send the planned actions to Miniflux
+    pub async fn execute(&self, client: &MinifluxClient) -> Result<()> {
+        // One batched call per status
+        for (change, entry_ids) in self.entries_by_status() {
+            client
+                .update_entries_status(entry_ids, change.as_str())
+                .await
+                .with_context(|| format!("Failed to set entries to {}", change.as_str()))?;
+        }
+
+        for &entry_id in &self.bookmark {
+            client
+                .toggle_bookmark(entry_id)
+                .await
+                .with_context(|| format!("Failed to bookmark entry {}", entry_id))?;
+        }
+
+        if !self.bookmark.is_empty() {
+            info!("Bookmarked {} entries", self.bookmark.len());
+        }
+
+        for &entry_id in &self.save {
+            client
+                .save_entry(entry_id)
+                .await
+                .with_context(|| format!("Failed to save entry {}", entry_id))?;
+            if let Some(history) = &self.history {
+                history.record(entry_id, SAVE_KEY);
+            }
+        }
+
+        if !self.save.is_empty() {
+            info!("Saved {} entries", self.save.len());
+        }
+
+        for (&entry_id, update) in &self.rewrites {
+            client
+                .update_entry(entry_id, update)
+                .await
+                .with_context(|| format!("Failed to rewrite entry {}", entry_id))?;
+        }
+
+        if !self.rewrites.is_empty() {
+            info!("Rewrote {} entries", self.rewrites.len());
+        }
+
+        self.send_webhooks().await;
+
+        Ok(())
+    }
+
+    /// Send the planned webhooks. A failed webhook should not hold back the
+    /// other actions; it is not recorded and will be retried on the next
+    /// cycle. Once a URL failed, its other deliveries wait for the next
+    /// cycle too, and so do all deliveries left after [`MAX_WEBHOOK_TIME`],
+    /// so a dead endpoint cannot stall the cycle for long.
+    async fn send_webhooks(&self) {
+        if self.webhooks.is_empty() {
+            return;
+        }
+
+        let http = reqwest::Client::new();
+        let started = Instant::now();
+        let mut failed: HashSet<&str> = HashSet::new();
+        let mut postponed = 0;
+        for (entry_id, delivery) in &self.webhooks {
+            let Some(remaining) = MAX_WEBHOOK_TIME.checked_sub(started.elapsed()) else {
+                postponed += 1;
+                continue;
+            };
+            if failed.contains(delivery.url.as_str()) {
+                postponed += 1;
+                continue;
+            }
+
+            let result = match tokio::time::timeout(remaining, delivery.send(&http)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("Out of time for webhooks in this cycle")),
+            };
+            match result {
+                Ok(()) => {
+                    if let Some(history) = &self.history {
+                        history.record(*entry_id, &webhook_key(&delivery.url));
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Webhook for entry {} to {} failed: {:#}",
+                        entry_id, delivery.url, e
+                    );
+                    failed.insert(&delivery.url);
+                }
+            }
+        }
+
+        if postponed > 0 {
+            warn!("Postponed {} webhooks to the next cycle", postponed);
+        }
+    }
+}
+
+/// Time the webhooks of one plan may take in total
+const MAX_WEBHOOK_TIME: Duration = Duration::from_secs(180);
+
+const SAVE_KEY: &str = "save";
+const FETCH_CONTENT_KEY: &str = "fetch-content";
+
+fn webhook_key(url: &str) -> String {
+    format!("webhook {}", url)
+}
+
+/// Apply a rewrite rule to a value. Returns `None` when the rewrite would not
+/// settle: applying it again to its own output must be a no-op, otherwise
+/// the entry would be rewritten again on every poll cycle.
+fn rewrite(rule_match: &RuleMatch, value: &str, replacement: &str) -> Option<String> {
+    let Some(re) = rule_match.rewrite_pattern() else {
+        warn!(
+            "Skipping {}: its rewrite pattern was not compiled",
+            rule_match
+        );
+        return None;
+    };
+
+    let rewritten = re.replace_all(value, replacement).into_owned();
+    if re.replace_all(&rewritten, replacement) != rewritten {
+        warn!(
+            "Skipping {}: rewriting with '{}' does not converge",
+            rule_match,
+            re.as_str()
+        );
+        return None;
+    }
+
+    Some(rewritten)
+}
+
+fn push_unique(ids: &mut Vec<u64>, id: u64) {
+    if !ids.contains(&id) {
+        ids.push(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_entry;
+    use crate::rules::{Condition, Field, Operator, Rule, RuleSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn compile(rule_set: RuleSet) -> CompiledRuleSet {
+        CompiledRuleSet::compile(rule_set).unwrap()
+    }
+
+    fn rule(action: Action) -> Rule {
+        Rule {
+            action: Some(action),
+            conditions: vec![
+                Condition {
+                    field: Field::Author,
+                    operator: Operator::Equals,
+                    value: "CTO".to_string(),
+                    ..Default::default()
+                }
+                .into(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn entry(id: u64, starred: bool) -> Entry {
+        Entry {
+            id,
+            author: "CTO".to_string(),
+            starred,
+            ..test_entry("Company update")
+        }
+    }
+
+    #[test]
+    fn test_plan_groups_entries_by_action() {
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(Action::Bookmark),
+                rule(Action::Bookmark),
+                rule(Action::MarkRead),
+            ],
+            ..Default::default()
+        });
+
+        let mut plan = ActionPlan::new();
+        for entry in [entry(1, false), entry(2, true)] {
+            let matches = evaluate_rule_sets(&[&rule_set], &entry);
+            plan.add(&entry, &matches);
+        }
+
+        assert_eq!(plan.matched(), 2);
+        assert_eq!(
+            plan.entries_by_status().get(&StatusChange::Read),
+            Some(&vec![1, 2])
+        );
+        // Entry 2 is already starred and must not be toggled back
+        assert_eq!(plan.bookmark, vec![1]);
+        assert_eq!(plan.summary(), "marked 2 as read, bookmarked 1");
+    }
+
+    #[test]
+    fn test_remove_wins_over_mark_read() {
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![rule(Action::MarkRead), rule(Action::Remove)],
+            ..Default::default()
+        });
+        let read_only = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![rule(Action::MarkRead)],
+            ..Default::default()
+        });
+
+        let mut plan = ActionPlan::new();
+        let removed = entry(1, false);
+        plan.add(&removed, &evaluate_rule_sets(&[&rule_set], &removed));
+        let read = entry(2, false);
+        plan.add(&read, &evaluate_rule_sets(&[&read_only], &read));
+
+        let grouped = plan.entries_by_status();
+        assert_eq!(grouped.get(&StatusChange::Removed), Some(&vec![1]));
+        assert_eq!(grouped.get(&StatusChange::Read), Some(&vec![2]));
+        assert_eq!(plan.summary(), "marked 1 as read, removed 1");
+    }
+
+    #[test]
+    fn test_rewrite_is_idempotent() {
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(Action::RewriteTitle {
+                    pattern: r"^\[Sponsored\]\s*(.*)$".to_string(),
+                    replacement: "$1".to_string(),
+                }),
+                rule(Action::RewriteContent {
+                    pattern: "content".to_string(),
+                    replacement: "more content".to_string(),
+                }),
+            ],
+            ..Default::default()
+        });
+
+        let mut sponsored = entry(1, false);
+        sponsored.title = "[Sponsored] Company update".to_string();
+        let mut plan = ActionPlan::new();
+        plan.add(&sponsored, &evaluate_rule_sets(&[&rule_set], &sponsored));
+
+        // The content rewrite would grow the content on every cycle
+        let update = plan.rewrites.get(&1).unwrap();
+        assert_eq!(update.title.as_deref(), Some("Company update"));
+        assert_eq!(update.content, None);
+        assert_eq!(plan.summary(), "rewrote 1");
+
+        // Once rewritten, the entry needs no further update
+        let cleaned = entry(1, false);
+        let mut plan = ActionPlan::new();
+        plan.add(&cleaned, &evaluate_rule_sets(&[&rule_set], &cleaned));
+        assert!(plan.is_empty());
+
+        // Patterns are compiled along with the rule set
+        let mut invalid = rule_set.rule_set().clone();
+        invalid.rules[0].action = Some(Action::RewriteTitle {
+            pattern: "(unclosed".to_string(),
+            replacement: String::new(),
+        });
+        assert!(CompiledRuleSet::compile(invalid).is_err());
+    }
+
+    #[test]
+    fn test_save_skips_saved_entries() {
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![rule(Action::Save)],
+            ..Default::default()
+        });
+        let history = Arc::new(ActionHistory::default());
+        history.record(1, SAVE_KEY);
+
+        let mut plan = ActionPlan::with_history(history);
+        for entry in [entry(1, false), entry(2, false)] {
+            plan.add(&entry, &evaluate_rule_sets(&[&rule_set], &entry));
+        }
+
+        assert_eq!(plan.save, vec![2]);
+        assert_eq!(plan.summary(), "saved 1");
+    }
+
+    #[test]
+    fn test_webhook_skips_delivered_entries() {
+        let webhook = Action::Webhook {
+            url: "https://chat.example.com/hook".to_string(),
+            secret: None,
+            retries: None,
+        };
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![rule(webhook.clone()), rule(webhook)],
+            ..Default::default()
+        });
+        let history = Arc::new(ActionHistory::default());
+        history.record(2, &webhook_key("https://chat.example.com/hook"));
+
+        let mut plan = ActionPlan::with_history(history);
+        for entry in [entry(1, false), entry(2, false)] {
+            plan.add(&entry, &evaluate_rule_sets(&[&rule_set], &entry));
+        }
+
+        // One delivery for entry 1 even though two rules matched, none for
+        // the entry that was already sent
+        assert_eq!(plan.webhooks.len(), 1);
+        let (entry_id, delivery) = &plan.webhooks[0];
+        assert_eq!(*entry_id, 1);
+        assert_eq!(delivery.retries, DEFAULT_RETRIES);
+        assert_eq!(delivery.payload.rule.label, "feed 123 rule 1");
+        assert_eq!(plan.summary(), "sent 1 to webhooks");
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_skip_failed_urls() {
+        let (server, requests) = failing_server();
+        let url = format!("{}/hook", server);
+
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![rule(Action::Webhook {
+                url: url.clone(),
+                secret: None,
+                retries: Some(0),
+            })],
+            ..Default::default()
+        });
+        let history = Arc::new(ActionHistory::default());
+        let mut plan = ActionPlan::with_history(history.clone());
+        for entry in [entry(1, false), entry(2, false), entry(3, false)] {
+            plan.add(&entry, &evaluate_rule_sets(&[&rule_set], &entry));
+        }
+        assert_eq!(plan.webhooks.len(), 3);
+
+        // The first failure postpones the other deliveries to that URL
+        plan.send_webhooks().await;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(!history.contains(1, &webhook_key(&url)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_content_fetch_is_not_repeated() {
+        let (server, requests) = failing_server();
+        let client = MinifluxClient::new(&crate::config::Config {
+            miniflux_url: server,
+            miniflux_token: "test-token".to_string(),
+            poll_interval: 300,
+            web_enabled: false,
+            web_port: 8080,
+        });
+        let rule_set = compile(RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            fetch_content: true,
+            rules: vec![rule(Action::MarkRead)],
+            ..Default::default()
+        });
+        let history = ActionHistory::default();
+
+        for _ in 0..2 {
+            let mut entry = entry(1, false);
+            let matches =
+                evaluate_with_full_content(&client, &history, &[&rule_set], &mut entry).await;
+            assert_eq!(matches.len(), 1);
+            assert_eq!(entry.content, "Some content");
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    /// An HTTP server failing every request, and the number of requests it got
+    fn failing_server() -> (String, Arc<AtomicUsize>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn test_history_forgets_entries_that_are_no_longer_unread() {
+        let history = ActionHistory::default();
+        history.record(1, SAVE_KEY);
+        history.record(2, SAVE_KEY);
+        history.record(2, &webhook_key("https://chat.example.com/hook"));
+
+        history.retain_entries(&HashSet::from([2, 3]));
+        assert!(!history.contains(1, SAVE_KEY));
+        assert!(history.contains(2, SAVE_KEY));
+        assert!(history.contains(2, &webhook_key("https://chat.example.com/hook")));
+    }
+}
@@ -14,6 +14,9 @@ where
     Ok(opt.unwrap_or_default())
 }
 
+/// Number of entries requested per page
+const ENTRIES_PAGE_SIZE: usize = 1000;
+
 #[derive(Debug, Clone)]
 pub struct MinifluxClient {
     client: Client,
@@ -33,19 +36,47 @@ pub struct Entry {
     #[serde(default)]
     pub author: String,
     pub status: String,
+    #[serde(default)]
+    pub starred: bool,
+    /// Estimated reading time in minutes
+    #[serde(default)]
+    pub reading_time: u64,
     pub feed: Feed,
     pub published_at: String,
     pub created_at: String,
     #[serde(default, deserialize_with = "deserialize_null_default")]
     pub tags: Vec<String>,
+    /// Attached media files, e.g. podcast episodes
+    #[serde(default, deserialize_with = "deserialize_null_default")]
+    pub enclosures: Vec<Enclosure>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Enclosure {
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub mime_type: String,
+    /// File size in bytes, 0 when the feed does not say. Signed like in
+    /// Miniflux, which passes on whatever length a feed claims.
+    #[serde(default)]
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Feed {
     pub id: u64,
     pub title: String,
     pub site_url: String,
     pub feed_url: String,
+    #[serde(default)]
+    pub category: Option<Category>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Category {
+    pub id: u64,
+    pub title: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,6 +91,19 @@ pub struct MarkEntriesRequest {
     pub status: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct FetchContentResponse {
+    content: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UpdateEntryRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
 impl MinifluxClient {
     pub fn new(config: &Config) -> Self {
         let client = Client::new();
@@ -98,135 +142,275 @@ impl MinifluxClient {
     pub async fn get_unread_entries(&self) -> Result<Vec<Entry>> {
         debug!("Fetching unread entries from Miniflux");
 
-        let url = format!("{}/v1/entries?status=unread&limit=1000", self.base_url);
+        let entries = self
+            .get_all_unread_entries("/v1/entries", "unread entries", ENTRIES_PAGE_SIZE)
+            .await?;
+
+        info!("Fetched {} unread entries", entries.len());
+        Ok(entries)
+    }
+
+    /// Fetch unread entries for a specific feed
+    pub async fn get_unread_entries_for_feed(&self, feed_id: u64) -> Result<Vec<Entry>> {
+        debug!("Fetching unread entries for feed {}", feed_id);
+
+        let entries = self
+            .get_all_unread_entries(
+                &format!("/v1/feeds/{}/entries", feed_id),
+                &format!("unread entries for feed {}", feed_id),
+                ENTRIES_PAGE_SIZE,
+            )
+            .await?;
+
+        debug!(
+            "Fetched {} unread entries for feed {}",
+            entries.len(),
+            feed_id
+        );
+        Ok(entries)
+    }
+
+    /// Fetch unread entries for all feeds of a category
+    pub async fn get_unread_entries_for_category(&self, category_id: u64) -> Result<Vec<Entry>> {
+        debug!("Fetching unread entries for category {}", category_id);
+
+        let entries = self
+            .get_all_unread_entries(
+                &format!("/v1/categories/{}/entries", category_id),
+                &format!("unread entries for category {}", category_id),
+                ENTRIES_PAGE_SIZE,
+            )
+            .await?;
+
+        debug!(
+            "Fetched {} unread entries for category {}",
+            entries.len(),
+            category_id
+        );
+        Ok(entries)
+    }
+
+    /// Fetch the unread entries of an entries endpoint page by page, oldest
+    /// first, until a page comes back short. Paging by entry ID rather than
+    /// offset keeps entries from being skipped or repeated when new ones
+    /// arrive in between.
+    async fn get_all_unread_entries(
+        &self,
+        path: &str,
+        what: &str,
+        page_size: usize,
+    ) -> Result<Vec<Entry>> {
+        let mut entries: Vec<Entry> = Vec::new();
+
+        loop {
+            let mut url = format!(
+                "{}{}?status=unread&order=id&direction=asc&limit={}",
+                self.base_url, path, page_size
+            );
+            if let Some(last) = entries.last() {
+                url.push_str(&format!("&after_entry_id={}", last.id));
+            }
+
+            let response = self
+                .client
+                .get(&url)
+                .header("X-Auth-Token", &self.token)
+                .send()
+                .await
+                .with_context(|| format!("Failed to fetch {}", what))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                anyhow::bail!("Failed to fetch {}: {} - {}", what, status, text);
+            }
+
+            let response_text = response
+                .text()
+                .await
+                .context("Failed to read response body")?;
+
+            let page: EntriesResponse = match serde_json::from_str(&response_text) {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("Failed to parse {} response. Error: {}", what, e);
+                    debug!("Raw response body: {}", response_text);
+                    anyhow::bail!("Failed to parse {} response: {}", what, e);
+                }
+            };
+
+            let short = page.entries.len() < page_size;
+            entries.extend(page.entries);
+            if short {
+                return Ok(entries);
+            }
+            debug!("Fetched {} of {} {}", entries.len(), page.total, what);
+        }
+    }
+
+    /// Fetch all feeds
+    pub async fn get_feeds(&self) -> Result<Vec<Feed>> {
+        debug!("Fetching feeds from Miniflux");
+
+        let url = format!("{}/v1/feeds", self.base_url);
         let response = self
             .client
             .get(&url)
             .header("X-Auth-Token", &self.token)
             .send()
             .await
-            .context("Failed to fetch unread entries")?;
+            .context("Failed to fetch feeds")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Failed to fetch unread entries: {} - {}", status, text);
+            anyhow::bail!("Failed to fetch feeds: {} - {}", status, text);
         }
 
-        let response_text = response
-            .text()
+        let feeds: Vec<Feed> = response
+            .json()
             .await
-            .context("Failed to read response body")?;
-
-        let entries_response: EntriesResponse = match serde_json::from_str(&response_text) {
-            Ok(response) => response,
-            Err(e) => {
-                debug!("Failed to parse entries response. Error: {}", e);
-                debug!("Raw response body: {}", response_text);
-                anyhow::bail!("Failed to parse entries response: {}", e);
-            }
-        };
+            .context("Failed to parse feeds response")?;
 
-        info!("Fetched {} unread entries", entries_response.entries.len());
-        Ok(entries_response.entries)
+        debug!("Fetched {} feeds", feeds.len());
+        Ok(feeds)
     }
 
-    /// Fetch unread entries for a specific feed
-    pub async fn get_unread_entries_for_feed(&self, feed_id: u64) -> Result<Vec<Entry>> {
-        debug!("Fetching unread entries for feed {}", feed_id);
+    /// Toggle the bookmark (star) flag of an entry
+    pub async fn toggle_bookmark(&self, entry_id: u64) -> Result<()> {
+        debug!("Toggling bookmark for entry {}", entry_id);
 
-        let url = format!(
-            "{}/v1/feeds/{}/entries?status=unread&limit=1000",
-            self.base_url, feed_id
-        );
+        let url = format!("{}/v1/entries/{}/bookmark", self.base_url, entry_id);
         let response = self
             .client
-            .get(&url)
+            .put(&url)
             .header("X-Auth-Token", &self.token)
             .send()
             .await
-            .context("Failed to fetch unread entries for feed")?;
+            .context("Failed to toggle bookmark")?;
 
-        if !response.status().is_success() {
+        if response.status().is_success() {
+            debug!("Successfully toggled bookmark for entry {}", entry_id);
+            Ok(())
+        } else {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
             anyhow::bail!(
-                "Failed to fetch unread entries for feed {}: {} - {}",
-                feed_id,
+                "Failed to toggle bookmark for entry {}: {} - {}",
+                entry_id,
                 status,
                 text
             );
         }
+    }
 
-        let response_text = response
-            .text()
-            .await
-            .context("Failed to read response body")?;
-
-        let entries_response: EntriesResponse = match serde_json::from_str(&response_text) {
-            Ok(response) => response,
-            Err(e) => {
-                debug!(
-                    "Failed to parse entries response for feed {}. Error: {}",
-                    feed_id, e
-                );
-                debug!("Raw response body: {}", response_text);
-                anyhow::bail!(
-                    "Failed to parse entries response for feed {}: {}",
-                    feed_id,
-                    e
-                );
-            }
-        };
+    /// Download the original article of an entry with Miniflux's scraper and
+    /// store it as the entry content. Returns the new content.
+    pub async fn fetch_original_content(&self, entry_id: u64) -> Result<String> {
+        debug!("Fetching original content for entry {}", entry_id);
 
-        debug!(
-            "Fetched {} unread entries for feed {}",
-            entries_response.entries.len(),
-            feed_id
+        let url = format!(
+            "{}/v1/entries/{}/fetch-content?update_content=true",
+            self.base_url, entry_id
         );
-        Ok(entries_response.entries)
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Auth-Token", &self.token)
+            .send()
+            .await
+            .context("Failed to fetch original content")?;
+
+        if response.status().is_success() {
+            let fetched: FetchContentResponse = response
+                .json()
+                .await
+                .context("Failed to parse fetched content")?;
+            debug!(
+                "Successfully fetched original content for entry {}",
+                entry_id
+            );
+            Ok(fetched.content)
+        } else {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Failed to fetch original content for entry {}: {} - {}",
+                entry_id,
+                status,
+                text
+            );
+        }
     }
 
-    /// Fetch all feeds
-    pub async fn get_feeds(&self) -> Result<Vec<Feed>> {
-        debug!("Fetching feeds from Miniflux");
+    /// Send an entry to the third-party services configured in Miniflux
+    pub async fn save_entry(&self, entry_id: u64) -> Result<()> {
+        debug!("Saving entry {}", entry_id);
 
-        let url = format!("{}/v1/feeds", self.base_url);
+        let url = format!("{}/v1/entries/{}/save", self.base_url, entry_id);
         let response = self
             .client
-            .get(&url)
+            .post(&url)
             .header("X-Auth-Token", &self.token)
             .send()
             .await
-            .context("Failed to fetch feeds")?;
+            .context("Failed to save entry")?;
 
-        if !response.status().is_success() {
+        if response.status().is_success() {
+            debug!("Successfully saved entry {}", entry_id);
+            Ok(())
+        } else {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Failed to fetch feeds: {} - {}", status, text);
+            anyhow::bail!("Failed to save entry {}: {} - {}", entry_id, status, text);
         }
+    }
 
-        let feeds: Vec<Feed> = response
-            .json()
+    /// Update the title and/or content of an entry
+    pub async fn update_entry(&self, entry_id: u64, update: &UpdateEntryRequest) -> Result<()> {
+        debug!("Updating entry {}", entry_id);
+
+        let url = format!("{}/v1/entries/{}", self.base_url, entry_id);
+        let response = self
+            .client
+            .put(&url)
+            .header("X-Auth-Token", &self.token)
+            .json(update)
+            .send()
             .await
-            .context("Failed to parse feeds response")?;
+            .context("Failed to update entry")?;
 
-        debug!("Fetched {} feeds", feeds.len());
-        Ok(feeds)
+        if response.status().is_success() {
+            debug!("Successfully updated entry {}", entry_id);
+            Ok(())
+        } else {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to update entry {}: {} - {}", entry_id, status, text);
+        }
     }
 
     /// Mark entries as read
     pub async fn mark_entries_as_read(&self, entry_ids: Vec<u64>) -> Result<()> {
+        self.update_entries_status(entry_ids, "read").await
+    }
+
+    /// Set the status ("read", "unread" or "removed") of entries
+    pub async fn update_entries_status(&self, entry_ids: Vec<u64>, status: &str) -> Result<()> {
         if entry_ids.is_empty() {
             return Ok(());
         }
 
-        debug!("Marking {} entries as read", entry_ids.len());
+        debug!(
+            "Setting status of {} entries to {}",
+            entry_ids.len(),
+            status
+        );
 
         let url = format!("{}/v1/entries", self.base_url);
         let request = MarkEntriesRequest {
             entry_ids: entry_ids.clone(),
-            status: "read".to_string(),
+            status: status.to_string(),
         };
 
         let response = self
@@ -236,19 +420,55 @@ impl MinifluxClient {
             .json(&request)
             .send()
             .await
-            .context("Failed to mark entries as read")?;
+            .with_context(|| format!("Failed to set entries status to {}", status))?;
 
         if response.status().is_success() {
-            info!("Successfully marked {} entries as read", entry_ids.len());
+            info!(
+                "Successfully set status of {} entries to {}",
+                entry_ids.len(),
+                status
+            );
             Ok(())
         } else {
-            let status = response.status();
+            let status_code = response.status();
             let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Failed to mark entries as read: {} - {}", status, text);
+            anyhow::bail!(
+                "Failed to set entries status to {}: {} - {}",
+                status,
+                status_code,
+                text
+            );
         }
     }
 }
 
+/// An unread entry of feed 123 for tests. Other fields are set with struct
+/// update syntax, e.g. `Entry { author: ..., ..test_entry("Title") }`.
+#[cfg(test)]
+pub(crate) fn test_entry(title: &str) -> Entry {
+    Entry {
+        id: 1,
+        title: title.to_string(),
+        url: "https://example.com".to_string(),
+        content: "Some content".to_string(),
+        author: "Author".to_string(),
+        status: "unread".to_string(),
+        starred: false,
+        reading_time: 0,
+        feed: Feed {
+            id: 123,
+            title: "Test Feed".to_string(),
+            site_url: "https://example.com".to_string(),
+            feed_url: "https://example.com/feed".to_string(),
+            category: None,
+        },
+        published_at: "2024-01-01T00:00:00Z".to_string(),
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        tags: vec![],
+        enclosures: vec![],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,4 +487,111 @@ mod tests {
         assert_eq!(client.base_url, "https://miniflux.example.com");
         assert_eq!(client.token, "test-token");
     }
+
+    #[test]
+    fn test_enclosure_deserialization() {
+        let entry = |enclosures: serde_json::Value| -> Entry {
+            serde_json::from_value(serde_json::json!({
+                "id": 1, "status": "unread",
+                "feed": {"id": 1, "title": "Podcast", "site_url": "", "feed_url": ""},
+                "published_at": "2024-01-01T00:00:00Z",
+                "created_at": "2024-01-01T00:00:00Z",
+                "enclosures": enclosures,
+            }))
+            .unwrap()
+        };
+
+        // Miniflux sends null when there are no enclosures at all, and
+        // passes on negative sizes from feeds
+        assert!(entry(serde_json::Value::Null).enclosures.is_empty());
+        let enclosures = entry(serde_json::json!([
+            {"url": "https://cdn.example.com/1.mp3", "mime_type": "audio/mpeg", "size": -1}
+        ]))
+        .enclosures;
+        assert_eq!(enclosures[0].size, -1);
+    }
+
+    /// Serve `entries` with Miniflux's entries API paging, `page_size` at a
+    /// time, and return the client and the requested paths
+    fn serve_entries(
+        entries: Vec<u64>,
+        page_size: usize,
+    ) -> (MinifluxClient, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = Config {
+            miniflux_url: format!("http://{}", listener.local_addr().unwrap()),
+            miniflux_token: "test-token".to_string(),
+            poll_interval: 300,
+            web_enabled: false,
+            web_port: 8080,
+        };
+
+        let server = std::thread::spawn(move || {
+            let mut paths = Vec::new();
+            // One full page and one short page
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap().to_string();
+                let after: u64 = path
+                    .split_once("after_entry_id=")
+                    .map_or(0, |(_, id)| id.parse().unwrap());
+                let page: Vec<_> = entries
+                    .iter()
+                    .filter(|&&id| id > after)
+                    .take(page_size)
+                    .map(|&id| {
+                        serde_json::json!({
+                            "id": id, "status": "unread",
+                            "feed": {"id": 1, "title": "Feed", "site_url": "", "feed_url": ""},
+                            "published_at": "2024-01-01T00:00:00Z",
+                            "created_at": "2024-01-01T00:00:00Z",
+                        })
+                    })
+                    .collect();
+                let body = serde_json::json!({"total": entries.len(), "entries": page}).to_string();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                paths.push(path);
+            }
+            paths
+        });
+
+        (MinifluxClient::new(&config), server)
+    }
+
+    #[tokio::test]
+    async fn test_entries_are_fetched_page_by_page() {
+        let (client, server) = serve_entries(vec![3, 5, 8], 2);
+
+        let entries = client
+            .get_all_unread_entries("/v1/feeds/1/entries", "unread entries", 2)
+            .await
+            .unwrap();
+        let ids: Vec<u64> = entries.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, [3, 5, 8]);
+
+        let paths = server.join().unwrap();
+        assert_eq!(
+            paths,
+            [
+                "/v1/feeds/1/entries?status=unread&order=id&direction=asc&limit=2",
+                "/v1/feeds/1/entries?status=unread&order=id&direction=asc&limit=2&after_entry_id=5",
+            ]
+        );
+    }
 }
@@ -0,0 +1,1687 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc, Weekday};
+use chrono_tz::Tz;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::{Level, debug, info, warn};
+
+use crate::api::Entry;
+use crate::dates::{
+    ActiveTimes, Hours, hour_in, parse_duration, parse_entry_timestamp, parse_timestamp,
+    parse_timezone, parse_weekdays, weekday_in,
+};
+use crate::fused::{NeedleSet, NeedleSetBuilder};
+use crate::html::{element_text, link_domains, parse_selector, select, url_domain};
+use crate::lists::{KeywordList, KeywordLists, LIST_EXTENSION, LISTS_DIR, validate_list_name};
+use crate::rules::{
+    Action, Condition, ConditionNode, Field, FieldKind, Operator, Rule, RuleMatch, RuleSet,
+};
+use crate::text::{TextMode, contains_phrase, normalize, within, words};
+
+/// A rule set prepared for evaluation: regexes are compiled and needles
+/// lowercased once when the rule file is loaded instead of for every entry
+#[derive(Debug)]
+pub struct CompiledRuleSet {
+    rule_set: RuleSet,
+    rules: Vec<Vec<CompiledNode>>,
+    /// When the rule set and each of its rules are active
+    active: ActiveTimes,
+    rules_active: Vec<ActiveTimes>,
+    /// The score threshold as a rule, see [`crate::rules::Scoring::rule`]
+    score_rule: Option<Rule>,
+    /// Patterns of rewrite actions, by rule with the score threshold last
+    rewrites: Vec<Option<Regex>>,
+    /// Substring searches shared by the `Fused` conditions of all rules
+    searches: Vec<FusedSearch>,
+    /// Keyword lists the rules refer to, as they were when compiled
+    lists: Vec<Arc<KeywordList>>,
+}
+
+/// The needles of all substring conditions on one field and text mode
+#[derive(Debug)]
+struct FusedSearch {
+    field: Field,
+    mode: TextMode,
+    needles: NeedleSet,
+}
+
+#[derive(Debug)]
+enum CompiledNode {
+    All(Vec<CompiledNode>),
+    Any(Vec<CompiledNode>),
+    Not(Box<CompiledNode>),
+    AtLeast(usize, Vec<CompiledNode>),
+    Condition(CompiledCondition),
+}
+
+/// A condition compiled for the kind of field it looks at
+#[derive(Debug)]
+enum CompiledCondition {
+    Text {
+        field: Field,
+        matcher: TextMatcher,
+    },
+    /// A field with several values, like tags
+    List {
+        field: Field,
+        matcher: TextMatcher,
+    },
+    /// Text or an attribute of the content elements a selector matches
+    Selected {
+        selector: Selector,
+        attribute: Option<String>,
+        matcher: TextMatcher,
+    },
+    Date {
+        field: Field,
+        matcher: DateMatcher,
+    },
+    Number {
+        field: Field,
+        matcher: NumberMatcher,
+    },
+    /// Substring conditions after fusion: matches when any of the needles
+    /// was found by the rule set's search, or none when negated
+    Fused {
+        search: usize,
+        needles: Vec<usize>,
+        negate: bool,
+    },
+}
+
+/// A text operator with its value normalized the way the field text will be
+#[derive(Debug)]
+struct TextMatcher {
+    mode: TextMode,
+    test: TextTest,
+}
+
+#[derive(Debug)]
+enum TextTest {
+    Contains(String),
+    NotContains(String),
+    Equals(String),
+    NotEquals(String),
+    StartsWith(String),
+    EndsWith(String),
+    /// Runs on text that keeps its case, the regex itself ignores case when
+    /// the condition is case-insensitive
+    Matches(Regex),
+    /// Terms of a keyword list, any of which may occur in the text
+    ContainsAny(Vec<String>),
+    /// Regexes of a keyword list, run like `Matches`
+    MatchesAny(RegexSet),
+    /// Words of a phrase that must appear consecutively
+    ContainsWord(Vec<String>),
+    /// Distinct words that must all appear within `distance` words
+    Near {
+        words: Vec<String>,
+        distance: usize,
+    },
+}
+
+/// A date operator with its parsed value
+#[derive(Debug)]
+enum DateMatcher {
+    OlderThan(Duration),
+    NewerThan(Duration),
+    Before(DateTime<Utc>),
+    After(DateTime<Utc>),
+    DayOfWeek(Vec<Weekday>, Tz),
+    HourOfDay(Hours, Tz),
+}
+
+/// A numeric operator with its parsed value
+#[derive(Debug)]
+enum NumberMatcher {
+    Equals(f64),
+    NotEquals(f64),
+    GreaterThan(f64),
+    LessThan(f64),
+    /// Inclusive range
+    Between(f64, f64),
+}
+
+impl CompiledRuleSet {
+    /// Compile a rule set that does not refer to keyword lists
+    pub fn compile(rule_set: RuleSet) -> Result<Self> {
+        Self::compile_with_lists(rule_set, &KeywordLists::default())
+    }
+
+    /// Compile a rule set, resolving keyword list conditions with `lists`.
+    /// Substring conditions looking at the same field are fused into one
+    /// Aho-Corasick search, so that hundreds of keywords cost about as much
+    /// as one.
+    pub fn compile_with_lists(rule_set: RuleSet, lists: &KeywordLists) -> Result<Self> {
+        let mut compiled = Self::compile_unfused(rule_set, lists)?;
+        compiled.searches = fuse(&mut compiled.rules)?;
+        Ok(compiled)
+    }
+
+    /// Compile without fusing substring conditions, each of them scans its
+    /// field on its own
+    fn compile_unfused(rule_set: RuleSet, lists: &KeywordLists) -> Result<Self> {
+        let tz = parse_timezone(rule_set.timezone.as_deref())?;
+        let rules = rule_set
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                rule.conditions
+                    .iter()
+                    .map(|node| compile_node(node, tz, Some(lists)))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("Failed to compile rule {}", i + 1))
+            })
+            .collect::<Result<_>>()?;
+
+        let active = rule_set.schedule.parse(tz)?;
+        let rules_active = rule_set
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                rule.schedule
+                    .parse(tz)
+                    .with_context(|| format!("Invalid schedule in rule {}", i + 1))
+            })
+            .collect::<Result<_>>()?;
+
+        let score_rule = rule_set.scoring.as_ref().map(|scoring| scoring.rule());
+        let rewrites = rule_set
+            .rules
+            .iter()
+            .chain(score_rule.as_ref())
+            .enumerate()
+            .map(|(i, rule)| match &rule.action {
+                Some(action) => compile_rewrite(action)
+                    .with_context(|| format!("Failed to compile rule {}", i + 1)),
+                None => Ok(None),
+            })
+            .collect::<Result<_>>()?;
+
+        let mut used_lists: Vec<Arc<KeywordList>> = Vec::new();
+        for condition in rule_set.conditions() {
+            if matches!(condition.operator, Operator::InList)
+                && let Some(list) = lists.get(&condition.value)
+                && !used_lists.iter().any(|used| Arc::ptr_eq(used, list))
+            {
+                used_lists.push(list.clone());
+            }
+        }
+
+        Ok(Self {
+            rule_set,
+            rules,
+            active,
+            rules_active,
+            score_rule,
+            rewrites,
+            searches: Vec::new(),
+            lists: used_lists,
+        })
+    }
+
+    /// The rule set this was compiled from
+    pub fn rule_set(&self) -> &RuleSet {
+        &self.rule_set
+    }
+
+    /// Evaluate all rules against an entry and return the indices of the
+    /// rules whose actions apply, see [`evaluate_rule_sets`]. The score
+    /// threshold has the index after the last rule.
+    pub fn evaluate(&self, entry: &Entry) -> Vec<usize> {
+        evaluate_rule_sets(&[self], entry)
+            .into_iter()
+            .map(|rule_match| rule_match.index)
+            .collect()
+    }
+
+    /// Evaluate the scored rules against an entry and return its score, or
+    /// `None` when the rule set has no scoring
+    pub fn score(&self, entry: &Entry) -> Option<ScoreCard> {
+        let text = EntryText::new(entry);
+        self.score_with(&Scan::new(&self.searches, &text))
+    }
+
+    fn score_with(&self, scan: &Scan) -> Option<ScoreCard> {
+        let scoring = self.rule_set.scoring.as_ref()?;
+        let breakdown: Vec<RuleScore> = self
+            .rule_set
+            .rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| Some((index, rule, rule.score?)))
+            .filter(|&(index, _, _)| {
+                self.rules_active[index].is_active(scan.text.now) && self.matches(index, scan)
+            })
+            .map(|(index, rule, score)| RuleScore {
+                rule: rule.key(index),
+                name: rule.name.clone(),
+                score,
+            })
+            .collect();
+
+        Some(ScoreCard {
+            total: breakdown.iter().map(|rule| i64::from(rule.score)).sum(),
+            threshold: scoring.threshold,
+            breakdown,
+        })
+    }
+
+    /// Rules whose actions may apply, by index. The score threshold comes
+    /// after the last rule, scored rules only count towards it.
+    fn acting_rules(&self) -> impl Iterator<Item = usize> + '_ {
+        let rules = &self.rule_set.rules;
+        (0..rules.len())
+            .filter(|&index| rules[index].score.is_none())
+            .chain(self.score_rule.as_ref().map(|_| rules.len()))
+    }
+
+    fn rule_match(&self, index: usize) -> RuleMatch<'_> {
+        let rule_match = match &self.score_rule {
+            Some(rule) if index == self.rules.len() => {
+                RuleMatch::score_threshold(&self.rule_set, rule)
+            }
+            _ => RuleMatch::new(&self.rule_set, index),
+        };
+        rule_match.with_rewrite(self.rewrites[index].as_ref())
+    }
+
+    fn is_active(&self, index: usize, now: DateTime<Utc>) -> bool {
+        index == self.rules.len() || self.rules_active[index].is_active(now)
+    }
+
+    fn matches(&self, index: usize, scan: &Scan) -> bool {
+        if index == self.rules.len() {
+            return self.score_with(scan).is_some_and(|card| card.reached());
+        }
+        // All top-level conditions must be true for the rule to match
+        self.rules[index].iter().all(|node| node.evaluate(scan))
+    }
+}
+
+/// The score of an entry in a rule set with scoring
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreCard {
+    /// Wider than the scores, so that adding them up cannot overflow
+    pub total: i64,
+    pub threshold: i32,
+    /// The scored rules the entry matches
+    pub breakdown: Vec<RuleScore>,
+}
+
+/// Score added by a matching rule
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleScore {
+    pub rule: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub score: i32,
+}
+
+impl ScoreCard {
+    /// Whether the total reaches the threshold, so the action applies
+    pub fn reached(&self) -> bool {
+        self.total >= i64::from(self.threshold)
+    }
+}
+
+impl fmt::Display for ScoreCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {}", self.total, self.threshold)?;
+        if !self.breakdown.is_empty() {
+            let parts: Vec<String> = self
+                .breakdown
+                .iter()
+                .map(|rule| format!("{} {:+}", rule.rule, rule.score))
+                .collect();
+            write!(f, " ({})", parts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compile the pattern of a rewrite action
+fn compile_rewrite(action: &Action) -> Result<Option<Regex>> {
+    match action {
+        Action::RewriteTitle { pattern, .. } | Action::RewriteContent { pattern, .. } => {
+            Regex::new(pattern)
+                .map(Some)
+                .with_context(|| format!("Invalid rewrite pattern '{}'", pattern))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Compile a condition tree node. Without `lists`, keyword list
+/// conditions only have their list name checked and never match.
+fn compile_node(
+    node: &ConditionNode,
+    tz: Tz,
+    lists: Option<&KeywordLists>,
+) -> Result<CompiledNode> {
+    let compile_all = |nodes: &[ConditionNode]| {
+        nodes
+            .iter()
+            .map(|node| compile_node(node, tz, lists))
+            .collect::<Result<_>>()
+    };
+
+    Ok(match node {
+        ConditionNode::All { all } => CompiledNode::All(compile_all(all)?),
+        ConditionNode::Any { any } => CompiledNode::Any(compile_all(any)?),
+        ConditionNode::Not { not } => CompiledNode::Not(Box::new(compile_node(not, tz, lists)?)),
+        ConditionNode::AtLeast { at_least, of } => {
+            CompiledNode::AtLeast(*at_least, compile_all(of)?)
+        }
+        ConditionNode::Condition(condition) => compile_condition(condition, tz, lists)?,
+    })
+}
+
+/// Check that a condition compiles: the operator suits the field and the
+/// value parses. Date values without an offset are taken to be in `tz`.
+pub(crate) fn check_condition(condition: &Condition, tz: Tz) -> Result<()> {
+    compile_condition(condition, tz, None).map(|_| ())
+}
+
+fn compile_condition(
+    condition: &Condition,
+    tz: Tz,
+    lists: Option<&KeywordLists>,
+) -> Result<CompiledNode> {
+    let field = condition.field.clone();
+    let kind = field.kind();
+
+    if !condition.operator.supports(kind) {
+        anyhow::bail!(
+            "Operator {:?} cannot be used on the {} field {:?}",
+            condition.operator,
+            kind,
+            field
+        );
+    }
+
+    if condition.distance.is_some() && !matches!(condition.operator, Operator::Near) {
+        anyhow::bail!("distance only applies to the near operator");
+    }
+
+    if (condition.selector.is_some() || condition.attribute.is_some())
+        && !matches!(field, Field::Content)
+    {
+        anyhow::bail!("selector and attribute only apply to the content field");
+    }
+
+    if condition.attribute.is_some() && condition.selector.is_none() {
+        anyhow::bail!("attribute needs a selector for the elements to read it from");
+    }
+
+    if kind != FieldKind::Text && (condition.case_sensitive.is_some() || condition.ignore_accents) {
+        anyhow::bail!(
+            "case_sensitive and ignore_accents only apply to text fields, not {:?}",
+            field
+        );
+    }
+
+    Ok(match kind {
+        FieldKind::Date => CompiledNode::Condition(CompiledCondition::Date {
+            field,
+            matcher: compile_date_matcher(condition, tz)?,
+        }),
+        FieldKind::Number => CompiledNode::Condition(CompiledCondition::Number {
+            field,
+            matcher: compile_number_matcher(condition)?,
+        }),
+        FieldKind::Text => {
+            let selector = condition
+                .selector
+                .as_deref()
+                .map(parse_selector)
+                .transpose()?;
+            let compile = |matcher| match &selector {
+                Some(selector) => CompiledCondition::Selected {
+                    selector: selector.clone(),
+                    attribute: condition.attribute.clone(),
+                    matcher,
+                },
+                None if is_list_field(&field) => CompiledCondition::List {
+                    field: field.clone(),
+                    matcher,
+                },
+                None => CompiledCondition::Text {
+                    field: field.clone(),
+                    matcher,
+                },
+            };
+
+            if matches!(condition.operator, Operator::InList) {
+                // A list matches when any of its terms or regexes does
+                CompiledNode::Any(
+                    compile_list_matchers(condition, lists)?
+                        .into_iter()
+                        .map(|matcher| CompiledNode::Condition(compile(matcher)))
+                        .collect(),
+                )
+            } else {
+                CompiledNode::Condition(compile(compile_text_matcher(condition)?))
+            }
+        }
+    })
+}
+
+/// Whether a text field has several values per entry
+fn is_list_field(field: &Field) -> bool {
+    matches!(
+        field,
+        Field::Tag | Field::LinkDomain | Field::EnclosureUrl | Field::EnclosureType
+    )
+}
+
+fn text_mode(condition: &Condition) -> TextMode {
+    // Regexes stay case-sensitive by default, as they always were
+    let is_regex = matches!(condition.operator, Operator::Matches);
+    TextMode {
+        case_sensitive: condition.case_sensitive.unwrap_or(is_regex),
+        ignore_accents: condition.ignore_accents,
+    }
+}
+
+/// Matchers for the terms and for the regexes of the list a condition
+/// refers to
+fn compile_list_matchers(
+    condition: &Condition,
+    lists: Option<&KeywordLists>,
+) -> Result<Vec<TextMatcher>> {
+    validate_list_name(&condition.value)?;
+    let Some(lists) = lists else {
+        return Ok(Vec::new());
+    };
+    let list = lists
+        .get(&condition.value)
+        .with_context(|| format!("Unknown keyword list '{}'", condition.value))?;
+
+    let mode = text_mode(condition);
+    let mut matchers = Vec::new();
+
+    if !list.terms().is_empty() {
+        let terms = list
+            .terms()
+            .iter()
+            .map(|term| normalize(term, mode))
+            .collect();
+        matchers.push(TextMatcher {
+            mode,
+            test: TextTest::ContainsAny(terms),
+        });
+    }
+
+    if !list.patterns().is_empty() {
+        let patterns = RegexSetBuilder::new(list.patterns())
+            .case_insensitive(!mode.case_sensitive)
+            .build()
+            .with_context(|| format!("Invalid regex in keyword list '{}'", list.name()))?;
+        matchers.push(TextMatcher {
+            mode,
+            test: TextTest::MatchesAny(patterns),
+        });
+    }
+
+    Ok(matchers)
+}
+
+/// Distance of a `near` condition that does not set one
+const DEFAULT_NEAR_DISTANCE: usize = 5;
+
+fn compile_text_matcher(condition: &Condition) -> Result<TextMatcher> {
+    let mode = text_mode(condition);
+    let needle = || normalize(&condition.value, mode);
+
+    let test = match condition.operator {
+        Operator::Contains => TextTest::Contains(needle()),
+        Operator::NotContains => TextTest::NotContains(needle()),
+        Operator::Equals => TextTest::Equals(needle()),
+        Operator::NotEquals => TextTest::NotEquals(needle()),
+        Operator::StartsWith => TextTest::StartsWith(needle()),
+        Operator::EndsWith => TextTest::EndsWith(needle()),
+        Operator::Matches => TextTest::Matches(
+            RegexBuilder::new(&condition.value)
+                .case_insensitive(!mode.case_sensitive)
+                .build()
+                .with_context(|| format!("Invalid regex pattern '{}'", condition.value))?,
+        ),
+        Operator::ContainsWord => {
+            let phrase = words(&needle());
+            if phrase.is_empty() {
+                anyhow::bail!("'{}' contains no words", condition.value);
+            }
+            TextTest::ContainsWord(phrase)
+        }
+        Operator::Near => {
+            let mut near = words(&needle());
+            near.sort();
+            near.dedup();
+            if near.len() < 2 {
+                anyhow::bail!(
+                    "near needs at least two different words, got '{}'",
+                    condition.value
+                );
+            }
+            TextTest::Near {
+                words: near,
+                distance: condition.distance.unwrap_or(DEFAULT_NEAR_DISTANCE),
+            }
+        }
+        _ => unreachable!("only text operators are supported on text fields"),
+    };
+
+    Ok(TextMatcher { mode, test })
+}
+
+fn compile_date_matcher(condition: &Condition, tz: Tz) -> Result<DateMatcher> {
+    let value = &condition.value;
+    Ok(match condition.operator {
+        Operator::OlderThan => DateMatcher::OlderThan(parse_duration(value)?),
+        Operator::NewerThan => DateMatcher::NewerThan(parse_duration(value)?),
+        Operator::Before => DateMatcher::Before(parse_timestamp(value, tz)?),
+        Operator::After => DateMatcher::After(parse_timestamp(value, tz)?),
+        Operator::DayOfWeek => DateMatcher::DayOfWeek(parse_weekdays(value)?, tz),
+        Operator::HourOfDay => DateMatcher::HourOfDay(Hours::parse(value)?, tz),
+        _ => unreachable!("only date operators are supported on date fields"),
+    })
+}
+
+fn compile_number_matcher(condition: &Condition) -> Result<NumberMatcher> {
+    let value = &condition.value;
+    Ok(match condition.operator {
+        Operator::Equals => NumberMatcher::Equals(parse_number(value)?),
+        Operator::NotEquals => NumberMatcher::NotEquals(parse_number(value)?),
+        Operator::GreaterThan => NumberMatcher::GreaterThan(parse_number(value)?),
+        Operator::LessThan => NumberMatcher::LessThan(parse_number(value)?),
+        Operator::Between => {
+            // The separator is the first dash after the minimum's own sign,
+            // so "-5-10" and "-10--5" work
+            let value = value.trim();
+            let (min, max) = value
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| c == '-')
+                .map(|(i, _)| (&value[..i], &value[i + 1..]))
+                .with_context(|| format!("Invalid range '{}', expected e.g. \"10-20\"", value))?;
+            let (min, max) = (parse_number(min)?, parse_number(max)?);
+            if min > max {
+                anyhow::bail!(
+                    "Invalid range '{}', the minimum is above the maximum",
+                    value
+                );
+            }
+            NumberMatcher::Between(min, max)
+        }
+        _ => unreachable!("only numeric operators are supported on numeric fields"),
+    })
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .with_context(|| format!("Invalid number '{}'", value.trim()))
+}
+
+impl CompiledNode {
+    fn evaluate(&self, scan: &Scan) -> bool {
+        match self {
+            CompiledNode::All(children) => children.iter().all(|child| child.evaluate(scan)),
+            CompiledNode::Any(children) => children.iter().any(|child| child.evaluate(scan)),
+            CompiledNode::Not(child) => !child.evaluate(scan),
+            CompiledNode::AtLeast(at_least, children) => {
+                // Stop counting as soon as the threshold is reached, or can
+                // no longer be
+                let mut matched = 0;
+                for (i, child) in children.iter().enumerate() {
+                    if child.evaluate(scan) {
+                        matched += 1;
+                        if matched >= *at_least {
+                            return true;
+                        }
+                    } else if matched + (children.len() - i - 1) < *at_least {
+                        return false;
+                    }
+                }
+                false
+            }
+            CompiledNode::Condition(condition) => condition.evaluate(scan),
+        }
+    }
+}
+
+impl CompiledCondition {
+    fn evaluate(&self, scan: &Scan) -> bool {
+        let text = scan.text;
+        match self {
+            CompiledCondition::Text { field, matcher } => matcher.evaluate_text(text, field),
+            CompiledCondition::List { field, matcher } => matcher.evaluate_list(text, field),
+            CompiledCondition::Selected {
+                selector,
+                attribute,
+                matcher,
+            } => matcher.evaluate_values(&select(
+                text.content_html(),
+                selector,
+                attribute.as_deref(),
+            )),
+            CompiledCondition::Date { field, matcher } => match text.date(field) {
+                Some(date) => matcher.evaluate(date, text.now),
+                // Entries without a valid date never match date conditions
+                None => false,
+            },
+            CompiledCondition::Number { field, matcher } => match field {
+                // Any enclosure may match, entries without one never do
+                Field::EnclosureSize => text
+                    .entry
+                    .enclosures
+                    .iter()
+                    .any(|enclosure| matcher.evaluate(enclosure.size as f64)),
+                field => matcher.evaluate(text.number(field)),
+            },
+            CompiledCondition::Fused {
+                search,
+                needles,
+                negate,
+            } => {
+                let found = scan.found(*search);
+                needles.iter().any(|&needle| found[needle]) != *negate
+            }
+        }
+    }
+}
+
+/// Replace the substring conditions of a rule set's rules by `Fused` ones,
+/// and return the searches they refer to
+fn fuse(rules: &mut [Vec<CompiledNode>]) -> Result<Vec<FusedSearch>> {
+    fn visit(node: &mut CompiledNode, builders: &mut Vec<(Field, TextMode, NeedleSetBuilder)>) {
+        let condition = match node {
+            CompiledNode::All(children)
+            | CompiledNode::Any(children)
+            | CompiledNode::AtLeast(_, children) => {
+                children.iter_mut().for_each(|child| visit(child, builders));
+                return;
+            }
+            CompiledNode::Not(child) => return visit(child, builders),
+            CompiledNode::Condition(condition) => condition,
+        };
+
+        let (CompiledCondition::Text { field, matcher }
+        | CompiledCondition::List { field, matcher }) = condition
+        else {
+            return;
+        };
+        let (needles, negate) = match &matcher.test {
+            TextTest::Contains(needle) => (std::slice::from_ref(needle), false),
+            TextTest::NotContains(needle) => (std::slice::from_ref(needle), true),
+            TextTest::ContainsAny(terms) => (terms.as_slice(), false),
+            _ => return,
+        };
+
+        let search = match builders
+            .iter()
+            .position(|(f, mode, _)| f == field && *mode == matcher.mode)
+        {
+            Some(search) => search,
+            None => {
+                builders.push((field.clone(), matcher.mode, NeedleSetBuilder::default()));
+                builders.len() - 1
+            }
+        };
+        let builder = &mut builders[search].2;
+        let needles = needles
+            .iter()
+            .map(|needle| builder.add(needle.clone()))
+            .collect();
+
+        *condition = CompiledCondition::Fused {
+            search,
+            needles,
+            negate,
+        };
+    }
+
+    let mut builders = Vec::new();
+    for node in rules.iter_mut().flatten() {
+        visit(node, &mut builders);
+    }
+
+    builders
+        .into_iter()
+        .map(|(field, mode, builder)| {
+            Ok(FusedSearch {
+                field,
+                mode,
+                needles: builder.build()?,
+            })
+        })
+        .collect()
+}
+
+/// An entry being evaluated against one rule set, with the results of the
+/// rule set's fused searches once they ran
+struct Scan<'s, 'e> {
+    text: &'s EntryText<'e>,
+    searches: &'s [FusedSearch],
+    found: Vec<OnceCell<Vec<bool>>>,
+}
+
+impl<'s, 'e> Scan<'s, 'e> {
+    fn new(searches: &'s [FusedSearch], text: &'s EntryText<'e>) -> Self {
+        Self {
+            text,
+            searches,
+            found: searches.iter().map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// Which needles of a search occur in its field
+    fn found(&self, search: usize) -> &[bool] {
+        self.found[search].get_or_init(|| {
+            let FusedSearch {
+                field,
+                mode,
+                needles,
+            } = &self.searches[search];
+            let haystack = if is_list_field(field) {
+                self.text.normalized_list_joined(field, *mode)
+            } else {
+                self.text.normalized(field, *mode)
+            };
+            needles.find_all(haystack)
+        })
+    }
+}
+
+impl TextMatcher {
+    fn evaluate_text(&self, text: &EntryText, field: &Field) -> bool {
+        match &self.test {
+            test if test.keeps_case() => {
+                test.evaluate(text.normalized(field, self.mode.keep_case()))
+            }
+            TextTest::ContainsWord(_) | TextTest::Near { .. } => {
+                self.test.evaluate_words(text.words(field, self.mode))
+            }
+            test => test.evaluate(text.normalized(field, self.mode)),
+        }
+    }
+
+    /// Substring operators look at all values joined together, the others
+    /// match when any single value does. Word operators never match across
+    /// two values.
+    fn evaluate_list(&self, text: &EntryText, field: &Field) -> bool {
+        match &self.test {
+            TextTest::Contains(_) | TextTest::NotContains(_) | TextTest::ContainsAny(_) => self
+                .test
+                .evaluate(text.normalized_list_joined(field, self.mode)),
+            TextTest::NotEquals(expected) => !text
+                .normalized_list(field, self.mode)
+                .iter()
+                .any(|value| value == expected),
+            test if test.keeps_case() => text
+                .normalized_list(field, self.mode.keep_case())
+                .iter()
+                .any(|value| test.evaluate(value)),
+            TextTest::ContainsWord(_) | TextTest::Near { .. } => text
+                .list_words(field, self.mode)
+                .iter()
+                .any(|value| self.test.evaluate_words(value)),
+            test => text
+                .normalized_list(field, self.mode)
+                .iter()
+                .any(|value| test.evaluate(value)),
+        }
+    }
+
+    /// Like `evaluate_list`, for values that are not cached per entry
+    fn evaluate_values(&self, values: &[String]) -> bool {
+        let normalized = |mode| {
+            values
+                .iter()
+                .map(|value| normalize(value, mode))
+                .collect::<Vec<_>>()
+        };
+        match &self.test {
+            TextTest::Contains(_) | TextTest::NotContains(_) | TextTest::ContainsAny(_) => {
+                self.test.evaluate(&normalized(self.mode).join(" "))
+            }
+            TextTest::NotEquals(expected) => {
+                !normalized(self.mode).iter().any(|value| value == expected)
+            }
+            test if test.keeps_case() => normalized(self.mode.keep_case())
+                .iter()
+                .any(|value| test.evaluate(value)),
+            TextTest::ContainsWord(_) | TextTest::Near { .. } => normalized(self.mode)
+                .iter()
+                .any(|value| self.test.evaluate_words(&words(value))),
+            test => normalized(self.mode)
+                .iter()
+                .any(|value| test.evaluate(value)),
+        }
+    }
+}
+
+impl TextTest {
+    /// Whether the test runs on text that keeps its case, which regexes do
+    fn keeps_case(&self) -> bool {
+        matches!(self, TextTest::Matches(_) | TextTest::MatchesAny(_))
+    }
+
+    /// Test normalized text
+    fn evaluate(&self, value: &str) -> bool {
+        match self {
+            TextTest::Contains(needle) => value.contains(needle.as_str()),
+            TextTest::NotContains(needle) => !value.contains(needle.as_str()),
+            TextTest::Equals(expected) => value == expected,
+            TextTest::NotEquals(expected) => value != expected,
+            TextTest::StartsWith(prefix) => value.starts_with(prefix.as_str()),
+            TextTest::EndsWith(suffix) => value.ends_with(suffix.as_str()),
+            TextTest::Matches(re) => re.is_match(value),
+            TextTest::ContainsAny(terms) => terms.iter().any(|term| value.contains(term.as_str())),
+            TextTest::MatchesAny(patterns) => patterns.is_match(value),
+            TextTest::ContainsWord(_) | TextTest::Near { .. } => {
+                unreachable!("word operators are evaluated on words")
+            }
+        }
+    }
+
+    /// Test the words of normalized text
+    fn evaluate_words(&self, value: &[String]) -> bool {
+        match self {
+            TextTest::ContainsWord(phrase) => contains_phrase(value, phrase),
+            TextTest::Near { words, distance } => within(value, words, *distance),
+            _ => unreachable!("{:?} does not test words", self),
+        }
+    }
+}
+
+impl NumberMatcher {
+    fn evaluate(&self, number: f64) -> bool {
+        match *self {
+            NumberMatcher::Equals(expected) => number == expected,
+            NumberMatcher::NotEquals(expected) => number != expected,
+            NumberMatcher::GreaterThan(limit) => number > limit,
+            NumberMatcher::LessThan(limit) => number < limit,
+            NumberMatcher::Between(min, max) => (min..=max).contains(&number),
+        }
+    }
+}
+
+impl DateMatcher {
+    fn evaluate(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self {
+            DateMatcher::OlderThan(age) => now - date > *age,
+            DateMatcher::NewerThan(age) => now - date < *age,
+            DateMatcher::Before(limit) => date < *limit,
+            DateMatcher::After(limit) => date >= *limit,
+            DateMatcher::DayOfWeek(days, tz) => days.contains(&weekday_in(date, *tz)),
+            DateMatcher::HourOfDay(hours, tz) => hours.contains(hour_in(date, *tz)),
+        }
+    }
+}
+
+/// The fields of an entry in the forms conditions compare against. Each
+/// form is computed at most once per entry, however many conditions and
+/// rule sets look at it.
+struct EntryText<'e> {
+    entry: &'e Entry,
+    /// Time of evaluation, for relative date conditions
+    now: DateTime<Utc>,
+    /// Normalized single value text fields, per text mode
+    normalized: [[OnceCell<String>; TextMode::COUNT]; 9],
+    /// Words of the normalized single value text fields
+    words: [[OnceCell<Vec<String>>; TextMode::COUNT]; 9],
+    /// Normalized tags, link domains, enclosure URLs and MIME types, per
+    /// text mode
+    normalized_lists: [[OnceCell<Vec<String>>; TextMode::COUNT]; 4],
+    normalized_lists_joined: [[OnceCell<String>; TextMode::COUNT]; 4],
+    list_words: [[OnceCell<Vec<Vec<String>>>; TextMode::COUNT]; 4],
+    /// Parsed publication and creation dates
+    dates: [OnceCell<Option<DateTime<Utc>>>; 2],
+    /// Parsed content HTML and the text extracted from it
+    content_html: OnceCell<Html>,
+    content_text: OnceCell<String>,
+    link_domains: OnceCell<Vec<String>>,
+    site_domain: OnceCell<String>,
+    /// Enclosure URLs and MIME types
+    enclosures: [OnceCell<Vec<String>>; 2],
+}
+
+impl<'e> EntryText<'e> {
+    fn new(entry: &'e Entry) -> Self {
+        Self {
+            entry,
+            now: Utc::now(),
+            normalized: Default::default(),
+            words: Default::default(),
+            normalized_lists: Default::default(),
+            normalized_lists_joined: Default::default(),
+            list_words: Default::default(),
+            dates: Default::default(),
+            content_html: OnceCell::new(),
+            content_text: OnceCell::new(),
+            link_domains: OnceCell::new(),
+            site_domain: OnceCell::new(),
+            enclosures: Default::default(),
+        }
+    }
+
+    fn slot(field: &Field) -> usize {
+        match field {
+            Field::Title => 0,
+            Field::Content => 1,
+            Field::RawContent => 2,
+            Field::Author => 3,
+            Field::Url => 4,
+            Field::FeedTitle => 5,
+            Field::FeedUrl => 6,
+            Field::SiteDomain => 7,
+            Field::CategoryTitle => 8,
+            _ => unreachable!("{:?} is not a single value text field", field),
+        }
+    }
+
+    fn raw(&self, field: &Field) -> &str {
+        match field {
+            Field::Title => &self.entry.title,
+            Field::Content => self.content_text(),
+            Field::RawContent => &self.entry.content,
+            Field::Author => &self.entry.author,
+            Field::Url => &self.entry.url,
+            Field::FeedTitle => &self.entry.feed.title,
+            Field::FeedUrl => &self.entry.feed.feed_url,
+            Field::SiteDomain => self.site_domain.get_or_init(|| {
+                // Some feeds do not set a site URL, fall back to the feed URL
+                url_domain(&self.entry.feed.site_url)
+                    .or_else(|| url_domain(&self.entry.feed.feed_url))
+                    .unwrap_or_default()
+            }),
+            Field::CategoryTitle => self
+                .entry
+                .feed
+                .category
+                .as_ref()
+                .map_or("", |category| &category.title),
+            _ => unreachable!("{:?} is not a single value text field", field),
+        }
+    }
+
+    fn list_slot(field: &Field) -> usize {
+        match field {
+            Field::Tag => 0,
+            Field::LinkDomain => 1,
+            Field::EnclosureUrl => 2,
+            Field::EnclosureType => 3,
+            _ => unreachable!("{:?} is not a list field", field),
+        }
+    }
+
+    fn raw_list(&self, field: &Field) -> &[String] {
+        match field {
+            Field::Tag => &self.entry.tags,
+            Field::LinkDomain => self
+                .link_domains
+                .get_or_init(|| link_domains(self.content_html())),
+            Field::EnclosureUrl => self.enclosures[0].get_or_init(|| {
+                self.entry
+                    .enclosures
+                    .iter()
+                    .map(|enclosure| enclosure.url.clone())
+                    .collect()
+            }),
+            Field::EnclosureType => self.enclosures[1].get_or_init(|| {
+                self.entry
+                    .enclosures
+                    .iter()
+                    .map(|enclosure| enclosure.mime_type.clone())
+                    .collect()
+            }),
+            _ => unreachable!("{:?} is not a list field", field),
+        }
+    }
+
+    fn normalized(&self, field: &Field, mode: TextMode) -> &str {
+        self.normalized[Self::slot(field)][mode.index()]
+            .get_or_init(|| normalize(self.raw(field), mode))
+    }
+
+    fn words(&self, field: &Field, mode: TextMode) -> &[String] {
+        self.words[Self::slot(field)][mode.index()]
+            .get_or_init(|| words(self.normalized(field, mode)))
+    }
+
+    fn normalized_list(&self, field: &Field, mode: TextMode) -> &[String] {
+        self.normalized_lists[Self::list_slot(field)][mode.index()].get_or_init(|| {
+            self.raw_list(field)
+                .iter()
+                .map(|value| normalize(value, mode))
+                .collect()
+        })
+    }
+
+    fn normalized_list_joined(&self, field: &Field, mode: TextMode) -> &str {
+        self.normalized_lists_joined[Self::list_slot(field)][mode.index()]
+            .get_or_init(|| self.normalized_list(field, mode).join(" "))
+    }
+
+    fn list_words(&self, field: &Field, mode: TextMode) -> &[Vec<String>] {
+        self.list_words[Self::list_slot(field)][mode.index()].get_or_init(|| {
+            self.normalized_list(field, mode)
+                .iter()
+                .map(|value| words(value))
+                .collect()
+        })
+    }
+
+    fn date(&self, field: &Field) -> Option<DateTime<Utc>> {
+        let (slot, value) = match field {
+            Field::Published => (0, &self.entry.published_at),
+            Field::Created => (1, &self.entry.created_at),
+            _ => unreachable!("{:?} is not a date field", field),
+        };
+        *self.dates[slot].get_or_init(|| parse_entry_timestamp(value))
+    }
+
+    fn number(&self, field: &Field) -> f64 {
+        match field {
+            Field::ReadingTime => self.entry.reading_time as f64,
+            Field::WordCount => self.content_text().split_whitespace().count() as f64,
+            Field::ContentLength => self.content_text().chars().count() as f64,
+            Field::EnclosureCount => self.entry.enclosures.len() as f64,
+            _ => unreachable!("{:?} is not a numeric field", field),
+        }
+    }
+
+    fn content_html(&self) -> &Html {
+        self.content_html
+            .get_or_init(|| Html::parse_fragment(&self.entry.content))
+    }
+
+    fn content_text(&self) -> &str {
+        self.content_text
+            .get_or_init(|| element_text(self.content_html().root_element()))
+    }
+}
+
+/// Evaluate several rule sets against an entry, e.g. a feed's own rule set
+/// together with the global ones, and return the rules whose actions apply.
+/// Rule sets and rules outside their schedule are skipped.
+///
+/// Rules are evaluated by descending priority, rules of equal priority in
+/// the order of their rule sets and files. Evaluation ends at the first
+/// matching rule that has `stop` set or the `keep` action. The score
+/// threshold of a rule set with scoring is evaluated like a rule of
+/// priority 0 placed after its last rule.
+pub fn evaluate_rule_sets<'a>(
+    rule_sets: &[&'a CompiledRuleSet],
+    entry: &Entry,
+) -> Vec<RuleMatch<'a>> {
+    let text = EntryText::new(entry);
+    let scans: Vec<Scan> = rule_sets
+        .iter()
+        .map(|compiled| Scan::new(&compiled.searches, &text))
+        .collect();
+
+    let mut order: Vec<(usize, usize)> = rule_sets
+        .iter()
+        .enumerate()
+        .filter(|(_, compiled)| {
+            compiled.rule_set.is_enabled() && compiled.active.is_active(text.now)
+        })
+        .flat_map(|(set, compiled)| {
+            compiled
+                .acting_rules()
+                .filter(|&index| compiled.is_active(index, text.now))
+                .map(move |index| (set, index))
+        })
+        .collect();
+    // Stable, so equal priorities keep their order
+    order.sort_by_key(|&(set, index)| Reverse(rule_sets[set].rule_match(index).rule().priority));
+
+    let mut matches = Vec::new();
+    let mut remaining = order.into_iter();
+
+    while let Some((set, index)) = remaining.next() {
+        let compiled = rule_sets[set];
+        let matched = if index < compiled.rules.len() {
+            compiled.matches(index, &scans[set])
+        } else if let Some(card) = compiled.score_with(&scans[set]) {
+            // Also evaluated for previews, so the engine logs the scores
+            // that reach the threshold at info level, see `process_feed`
+            debug!(
+                "Entry {} scored {} in {} rule set",
+                entry.id,
+                card,
+                compiled.rule_set.scope()
+            );
+            card.reached()
+        } else {
+            false
+        };
+        if !matched {
+            continue;
+        }
+
+        let rule_match = compiled.rule_match(index);
+        debug!("Entry {} matches {}", entry.id, rule_match);
+        matches.push(rule_match);
+
+        let rule = rule_match.rule();
+        let reason = match rule.action {
+            Some(Action::Keep) => "kept",
+            _ if rule.stop => "stopped",
+            _ => continue,
+        };
+
+        if !tracing::enabled!(Level::INFO) {
+            break;
+        }
+
+        // Name the rules that would have matched too, to explain why their
+        // actions did not happen. Evaluating them is only worth it when the
+        // explanation gets logged.
+        let skipped: Vec<String> = remaining
+            .filter(|&(set, index)| rule_sets[set].matches(index, &scans[set]))
+            .map(|(set, index)| rule_sets[set].rule_match(index).to_string())
+            .collect();
+        if skipped.is_empty() {
+            debug!("Entry {} {} by {}", entry.id, reason, rule_match);
+        } else {
+            info!(
+                "Entry {} {} by {}, skipping {}",
+                entry.id,
+                reason,
+                rule_match,
+                skipped.join(", ")
+            );
+        }
+        break;
+    }
+
+    matches
+}
+
+/// Compiled rule sets of a rules directory, keyed by file. A file is only
+/// parsed and compiled again when its modification time or size changes,
+/// or when a keyword list it refers to does.
+#[derive(Debug, Default)]
+pub struct RuleSetCache {
+    files: Mutex<HashMap<PathBuf, CachedFile>>,
+    lists: Mutex<HashMap<PathBuf, CachedList>>,
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    modified: SystemTime,
+    len: u64,
+    compiled: Arc<CompiledRuleSet>,
+}
+
+#[derive(Debug)]
+struct CachedList {
+    modified: SystemTime,
+    len: u64,
+    list: Arc<KeywordList>,
+}
+
+/// Paths of the files with the given extension in a directory, sorted
+fn files_with_extension(dir_path: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir_path)
+        .with_context(|| format!("Failed to read directory: {}", dir_path.display()))?
+    {
+        let path = entry.context("Failed to read directory entry")?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some(extension) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Modification time and size of a file, to tell whether it changed
+fn file_version(path: &Path) -> Result<(SystemTime, u64)> {
+    let metadata = fs::metadata(path)?;
+    Ok((
+        metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        metadata.len(),
+    ))
+}
+
+impl RuleSetCache {
+    /// Load the rule sets of a directory, reusing the compiled rule sets of
+    /// files that did not change since the last call
+    pub fn load<P: AsRef<Path>>(&self, dir_path: P) -> Result<Vec<Arc<CompiledRuleSet>>> {
+        let dir_path = dir_path.as_ref();
+
+        if !dir_path.exists() {
+            info!(
+                "Rules directory {} does not exist, creating it",
+                dir_path.display()
+            );
+            fs::create_dir_all(dir_path).with_context(|| {
+                format!("Failed to create rules directory: {}", dir_path.display())
+            })?;
+        }
+
+        let lists = self.load_lists(&dir_path.join(LISTS_DIR))?;
+        let paths = files_with_extension(dir_path, "toml")?;
+
+        let mut files = self.files.lock().unwrap();
+        // Forget files that were deleted
+        files.retain(|path, _| paths.contains(path));
+
+        let mut rule_sets = Vec::new();
+
+        for path in paths {
+            let (modified, len) = match file_version(&path) {
+                Ok(version) => version,
+                Err(e) => {
+                    warn!("Failed to read rule file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            if let Some(cached) = files.get(&path)
+                && cached.modified == modified
+                && cached.len == len
+                && cached.compiled.lists.iter().all(|list| {
+                    lists
+                        .get(list.name())
+                        .is_some_and(|current| Arc::ptr_eq(current, list))
+                })
+            {
+                rule_sets.push(cached.compiled.clone());
+                continue;
+            }
+
+            match RuleSet::load_from_file(&path)
+                .and_then(|rule_set| CompiledRuleSet::compile_with_lists(rule_set, &lists))
+            {
+                Ok(compiled) => {
+                    debug!("Compiled rule set from {}", path.display());
+                    let compiled = Arc::new(compiled);
+                    rule_sets.push(compiled.clone());
+                    files.insert(
+                        path,
+                        CachedFile {
+                            modified,
+                            len,
+                            compiled,
+                        },
+                    );
+                }
+                Err(e) => {
+                    warn!("Failed to load rule file {}: {}", path.display(), e);
+                    files.remove(&path);
+                }
+            }
+        }
+
+        debug!(
+            "Loaded {} rule sets from {}",
+            rule_sets.len(),
+            dir_path.display()
+        );
+        Ok(rule_sets)
+    }
+
+    /// Compile a rule set that is not saved in the directory, e.g. to
+    /// preview it, with the directory's keyword lists
+    pub fn compile<P: AsRef<Path>>(
+        &self,
+        dir_path: P,
+        rule_set: RuleSet,
+    ) -> Result<CompiledRuleSet> {
+        let lists = self.load_lists(&dir_path.as_ref().join(LISTS_DIR))?;
+        CompiledRuleSet::compile_with_lists(rule_set, &lists)
+    }
+
+    /// Load the keyword lists of the lists folder, reusing the lists of
+    /// files that did not change since the last call
+    fn load_lists(&self, dir_path: &Path) -> Result<KeywordLists> {
+        let mut cached_lists = self.lists.lock().unwrap();
+        if !dir_path.is_dir() {
+            cached_lists.clear();
+            return Ok(KeywordLists::default());
+        }
+
+        let paths = files_with_extension(dir_path, LIST_EXTENSION)?;
+        cached_lists.retain(|path, _| paths.contains(path));
+
+        let mut lists = KeywordLists::default();
+        for path in paths {
+            let (modified, len) = match file_version(&path) {
+                Ok(version) => version,
+                Err(e) => {
+                    warn!("Failed to read list file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            if let Some(cached) = cached_lists.get(&path)
+                && cached.modified == modified
+                && cached.len == len
+            {
+                lists.insert(cached.list.clone());
+                continue;
+            }
+
+            match KeywordList::load_from_file(&path) {
+                Ok(list) => {
+                    debug!("Loaded keyword list from {}", path.display());
+                    let list = Arc::new(list);
+                    lists.insert(list.clone());
+                    cached_lists.insert(
+                        path,
+                        CachedList {
+                            modified,
+                            len,
+                            list,
+                        },
+                    );
+                }
+                Err(e) => {
+                    warn!("Failed to load list file {}: {:#}", path.display(), e);
+                    cached_lists.remove(&path);
+                }
+            }
+        }
+
+        Ok(lists)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_entry;
+    use crate::rules::{Action, Rule};
+
+    fn rule_set(feed_id: u64, value: &str) -> RuleSet {
+        RuleSet {
+            feed_id: Some(feed_id),
+            enabled: true,
+            rules: vec![Rule {
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: value.to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = RuleSetCache::default();
+        rule_set(1, "sponsored")
+            .save_to_file(temp_dir.path().join("feed_1.toml"))
+            .unwrap();
+        rule_set(2, "ad")
+            .save_to_file(temp_dir.path().join("feed_2.toml"))
+            .unwrap();
+
+        let first = cache.load(temp_dir.path()).unwrap();
+        let second = cache.load(temp_dir.path()).unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(Arc::ptr_eq(&first[0], &second[0]));
+        assert!(Arc::ptr_eq(&first[1], &second[1]));
+
+        // A changed file is compiled again, a deleted one is dropped
+        rule_set(1, "sponsored content")
+            .save_to_file(temp_dir.path().join("feed_1.toml"))
+            .unwrap();
+        fs::remove_file(temp_dir.path().join("feed_2.toml")).unwrap();
+        let third = cache.load(temp_dir.path()).unwrap();
+        assert_eq!(third.len(), 1);
+        assert!(!Arc::ptr_eq(&first[0], &third[0]));
+        let Some(ConditionNode::Condition(condition)) =
+            third[0].rule_set().rules[0].conditions.first()
+        else {
+            panic!("expected a single condition");
+        };
+        assert_eq!(condition.value, "sponsored content");
+    }
+
+    #[test]
+    fn test_keyword_lists() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = RuleSetCache::default();
+        let lists_dir = temp_dir.path().join(LISTS_DIR);
+        fs::create_dir(&lists_dir).unwrap();
+        fs::write(
+            lists_dir.join("spam.txt"),
+            "# Spam\ncasino\n/free (money|coins)/\n",
+        )
+        .unwrap();
+
+        let mut spam = rule_set(1, "spam");
+        if let ConditionNode::Condition(condition) = &mut spam.rules[0].conditions[0] {
+            condition.operator = Operator::InList;
+        }
+        spam.save_to_file(temp_dir.path().join("feed_1.toml"))
+            .unwrap();
+
+        let first = cache.load(temp_dir.path()).unwrap();
+        assert_eq!(first[0].evaluate(&test_entry("New CASINO opens")), vec![0]);
+        assert_eq!(
+            first[0].evaluate(&test_entry("Free Money for all")),
+            vec![0]
+        );
+        assert!(first[0].evaluate(&test_entry("Free software")).is_empty());
+
+        // Changing the list compiles the rule set again
+        fs::write(lists_dir.join("spam.txt"), "casino\nsoftware\n").unwrap();
+        let second = cache.load(temp_dir.path()).unwrap();
+        assert!(!Arc::ptr_eq(&first[0], &second[0]));
+        assert_eq!(second[0].evaluate(&test_entry("Free software")), vec![0]);
+        assert!(
+            second[0]
+                .evaluate(&test_entry("Free Money for all"))
+                .is_empty()
+        );
+
+        // Rule sets referring to a missing list are not loaded
+        fs::remove_file(lists_dir.join("spam.txt")).unwrap();
+        assert!(cache.load(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_between_ranges() {
+        let between = |value: &str| {
+            compile_number_matcher(&Condition {
+                field: Field::ReadingTime,
+                operator: Operator::Between,
+                value: value.to_string(),
+                ..Default::default()
+            })
+        };
+        let contains = |value: &str, number: f64| between(value).unwrap().evaluate(number);
+
+        assert!(contains("10-20", 10.0));
+        assert!(contains(" 10 - 20 ", 20.0));
+        assert!(!contains("10-20", 21.0));
+        assert!(contains("-5-10", -5.0));
+        assert!(contains("-5-10", 0.0));
+        assert!(contains("-10--5", -7.5));
+        assert!(!contains("-10--5", -4.0));
+        assert!(between("10").is_err());
+        assert!(between("-10").is_err());
+        assert!(between("20-10").is_err());
+    }
+
+    /// Pseudo-random rules on many keywords and entries to evaluate them on,
+    /// the same on every run. Rules mix fused and unfused conditions, text
+    /// modes, fields and groups.
+    fn keyword_corpus(rules: usize, entries: usize) -> (RuleSet, KeywordLists, Vec<Entry>) {
+        const SYLLABLES: &[&str] = &[
+            "ka", "Rö", "ßt", "mi", "AI", "zu", "crypto", "to", "ken", "é", "air", "drop", " ",
+        ];
+        let mut seed = 42u64;
+        let mut next = move |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+        let mut text = |syllables: usize| -> String {
+            (0..syllables)
+                .map(|_| SYLLABLES[next(SYLLABLES.len())])
+                .collect()
+        };
+
+        let condition = |field: Field, operator: Operator, value: String| Condition {
+            field,
+            operator,
+            value,
+            ..Default::default()
+        };
+        let rules = (0..rules)
+            .map(|i| {
+                let node: ConditionNode = match i % 7 {
+                    0 => condition(Field::Title, Operator::Contains, text(2)).into(),
+                    1 => condition(Field::Content, Operator::Contains, text(3)).into(),
+                    2 => ConditionNode::All {
+                        all: vec![
+                            condition(Field::Title, Operator::NotContains, text(1)).into(),
+                            condition(Field::Content, Operator::Contains, text(2)).into(),
+                        ],
+                    },
+                    3 => ConditionNode::Any {
+                        any: vec![
+                            condition(Field::Tag, Operator::Contains, text(2)).into(),
+                            Condition {
+                                case_sensitive: Some(true),
+                                ..condition(Field::Title, Operator::Contains, text(2))
+                            }
+                            .into(),
+                        ],
+                    },
+                    4 => ConditionNode::Not {
+                        not: Box::new(
+                            Condition {
+                                ignore_accents: true,
+                                ..condition(Field::Content, Operator::Contains, text(2))
+                            }
+                            .into(),
+                        ),
+                    },
+                    5 => ConditionNode::AtLeast {
+                        at_least: 2,
+                        of: vec![
+                            condition(Field::Title, Operator::Contains, text(1)).into(),
+                            condition(Field::Content, Operator::Contains, text(1)).into(),
+                            condition(Field::Content, Operator::NotContains, text(2)).into(),
+                        ],
+                    },
+                    _ => condition(Field::Title, Operator::InList, "words".to_string()).into(),
+                };
+                Rule {
+                    action: Some(Action::MarkRead),
+                    conditions: vec![node],
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let rule_set = RuleSet {
+            feed_id: Some(1),
+            enabled: true,
+            rules,
+            ..Default::default()
+        };
+
+        let list: Vec<String> = (0..20)
+            .map(|_| text(2))
+            .chain(["/ka+ro/".to_string()])
+            .collect();
+        let mut lists = KeywordLists::default();
+        lists.insert(Arc::new(
+            KeywordList::parse("words", &list.join("\n")).unwrap(),
+        ));
+
+        let entries = (0..entries)
+            .map(|id| {
+                let title = text(8);
+                let content = format!("<p>{}</p>", text(40));
+                Entry {
+                    id: id as u64,
+                    content,
+                    tags: vec![text(2), text(2)],
+                    ..test_entry(&title)
+                }
+            })
+            .collect();
+
+        (rule_set, lists, entries)
+    }
+
+    #[test]
+    fn test_fused_matches_unfused() {
+        let (rule_set, lists, entries) = keyword_corpus(300, 200);
+        let fused = CompiledRuleSet::compile_with_lists(rule_set.clone(), &lists).unwrap();
+        let unfused = CompiledRuleSet::compile_unfused(rule_set, &lists).unwrap();
+        assert!(!fused.searches.is_empty());
+        assert!(unfused.searches.is_empty());
+
+        let mut matches = 0;
+        for entry in &entries {
+            let expected = unfused.evaluate(entry);
+            assert_eq!(fused.evaluate(entry), expected, "entry {}", entry.id);
+            matches += expected.len();
+        }
+        // The corpus is only useful if rules match some entries but not all
+        assert!(matches > 0 && matches < 300 * 200);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_` to
+    /// compare evaluation times as keyword counts grow
+    #[test]
+    #[ignore]
+    fn bench_fused_keywords() {
+        for rules in [10, 100, 1000, 5000] {
+            let (rule_set, lists, entries) = keyword_corpus(rules, 500);
+            let fused = CompiledRuleSet::compile_with_lists(rule_set.clone(), &lists).unwrap();
+            let unfused = CompiledRuleSet::compile_unfused(rule_set, &lists).unwrap();
+
+            let time = |compiled: &CompiledRuleSet| {
+                let start = std::time::Instant::now();
+                let results: Vec<_> = entries
+                    .iter()
+                    .map(|entry| compiled.evaluate(entry))
+                    .collect();
+                (start.elapsed(), results)
+            };
+            let (fused_time, fused_results) = time(&fused);
+            let (unfused_time, unfused_results) = time(&unfused);
+            assert_eq!(fused_results, unfused_results);
+            println!(
+                "{:>5} rules: fused {:>10.2?}, unfused {:>10.2?}",
+                rules, fused_time, unfused_time
+            );
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// Parse a timezone name such as "Europe/Berlin", defaulting to UTC
+pub fn parse_timezone(name: Option<&str>) -> Result<Tz> {
+    match name {
+        Some(name) => name
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", name)),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// Parse an entry timestamp as sent by Miniflux
+pub fn parse_entry_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Parse a duration like "90m", "12h", "2d" or "1w2d"
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let mut total = Duration::zero();
+    let mut digits = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let amount: i64 = digits
+            .parse()
+            .with_context(|| format!("Invalid duration '{}'", value))?;
+        digits.clear();
+
+        let part = match c {
+            's' => Duration::try_seconds(amount),
+            'm' => Duration::try_minutes(amount),
+            'h' => Duration::try_hours(amount),
+            'd' => Duration::try_days(amount),
+            'w' => Duration::try_weeks(amount),
+            _ => anyhow::bail!(
+                "Invalid duration unit '{}' in '{}', expected s, m, h, d or w",
+                c,
+                value
+            ),
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .with_context(|| format!("Duration '{}' is too long", value))?;
+    }
+
+    if !digits.is_empty() || value.is_empty() {
+        anyhow::bail!(
+            "Invalid duration '{}', expected e.g. \"12h\" or \"2d\"",
+            value
+        );
+    }
+
+    Ok(total)
+}
+
+/// Parse a point in time: RFC 3339, or a date or date and time without
+/// offset, which is taken to be in the rule set's timezone
+pub fn parse_timestamp(value: &str, tz: Tz) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+        })
+        .with_context(|| {
+            format!(
+                "Invalid timestamp '{}', expected e.g. \"2024-07-20\" or \"2024-07-20T18:00\"",
+                value
+            )
+        })?;
+
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .with_context(|| format!("'{}' does not exist in timezone {}", value, tz))
+}
+
+/// Parse a comma separated list of weekdays, e.g. "sat, sun"
+pub fn parse_weekdays(value: &str) -> Result<Vec<Weekday>> {
+    value
+        .split(',')
+        .map(|day| {
+            day.trim()
+                .parse::<Weekday>()
+                .map_err(|_| anyhow::anyhow!("Invalid day of week '{}'", day.trim()))
+        })
+        .collect()
+}
+
+/// A set of hours of the day, written as comma separated hours and ranges
+/// such as "9-17" or "22-6". Ranges include the start and exclude the end,
+/// and wrap around midnight when the end is smaller than the start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hours(Vec<(u32, u32)>);
+
+impl Hours {
+    pub fn parse(value: &str) -> Result<Self> {
+        let parse_hour = |hour: &str, max: u32| -> Result<u32> {
+            let hour: u32 = hour
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid hour '{}'", hour.trim()))?;
+            if hour > max {
+                anyhow::bail!("Hour {} is out of range, expected 0 to {}", hour, max);
+            }
+            Ok(hour)
+        };
+
+        let ranges = value
+            .split(',')
+            .map(|part| match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse_hour(start, 23)?, parse_hour(end, 24)?);
+                    if start == end {
+                        anyhow::bail!(
+                            "Empty hour range '{}', the end is excluded, e.g. \"{}-{}\" is one hour",
+                            part.trim(),
+                            start,
+                            start + 1
+                        );
+                    }
+                    Ok((start, end))
+                }
+                None => {
+                    let hour = parse_hour(part, 23)?;
+                    Ok((hour, hour + 1))
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self(ranges))
+    }
+
+    pub fn contains(&self, hour: u32) -> bool {
+        self.0.iter().any(|&(start, end)| {
+            if start <= end {
+                (start..end).contains(&hour)
+            } else {
+                hour >= start || hour < end
+            }
+        })
+    }
+}
+
+/// When a rule is active: a window between two points in time and a weekly
+/// schedule of days and hours. Unset parts do not restrict anything.
+#[derive(Debug, Clone)]
+pub struct ActiveTimes {
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub days: Option<Vec<Weekday>>,
+    pub hours: Option<Hours>,
+    /// Timezone of the days and hours
+    pub tz: Tz,
+}
+
+impl ActiveTimes {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.from.is_none_or(|from| now >= from)
+            && self.until.is_none_or(|until| now < until)
+            && self
+                .days
+                .as_ref()
+                .is_none_or(|days| days.contains(&weekday_in(now, self.tz)))
+            && self
+                .hours
+                .as_ref()
+                .is_none_or(|hours| hours.contains(hour_in(now, self.tz)))
+    }
+
+    /// Whether the window is over, so the rule will never be active again
+    pub fn has_ended(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_some_and(|until| until <= now)
+    }
+}
+
+/// Weekday of a timestamp in the given timezone
+pub fn weekday_in(dt: DateTime<Utc>, tz: Tz) -> Weekday {
+    dt.with_timezone(&tz).weekday()
+}
+
+/// Hour of a timestamp in the given timezone
+pub fn hour_in(dt: DateTime<Utc>, tz: Tz) -> u32 {
+    dt.with_timezone(&tz).hour()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1w2d").unwrap(), Duration::days(9));
+        assert!(parse_duration("2").is_err());
+        assert!(parse_duration("2y").is_err());
+        assert!(parse_duration("").is_err());
+        // Out of range durations are errors rather than panics
+        assert!(parse_duration("99999999999999w").is_err());
+        assert!(parse_duration("100000000000d100000000000d").is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp_in_timezone() {
+        let berlin = parse_timezone(Some("Europe/Berlin")).unwrap();
+        assert_eq!(
+            parse_timestamp("2024-07-20", berlin).unwrap(),
+            parse_timestamp("2024-07-19T22:00:00Z", Tz::UTC).unwrap()
+        );
+        assert!(parse_timestamp("20.07.2024", berlin).is_err());
+        assert!(parse_timezone(Some("Mars/Olympus")).is_err());
+    }
+
+    #[test]
+    fn test_hours_wrap_around_midnight() {
+        let night = Hours::parse("22-6").unwrap();
+        assert!(night.contains(23));
+        assert!(night.contains(0));
+        assert!(!night.contains(6));
+        assert!(!night.contains(12));
+
+        let hours = Hours::parse("9, 12-14").unwrap();
+        assert!(hours.contains(9));
+        assert!(!hours.contains(10));
+        assert!(hours.contains(13));
+        assert!(Hours::parse("25").is_err());
+    }
+
+    #[test]
+    fn test_hours_reject_empty_ranges() {
+        let error = Hours::parse("9-9").unwrap_err().to_string();
+        assert!(error.contains("Empty hour range '9-9'"), "{}", error);
+        assert!(Hours::parse("8, 0-0").is_err());
+        assert!(Hours::parse("9-10").unwrap().contains(9));
+    }
+
+    #[test]
+    fn test_active_times() {
+        let berlin = parse_timezone(Some("Europe/Berlin")).unwrap();
+        let at = |value| parse_timestamp(value, berlin).unwrap();
+        let evenings = ActiveTimes {
+            from: Some(at("2024-06-14")),
+            until: Some(at("2024-07-15")),
+            days: Some(parse_weekdays("sat,sun").unwrap()),
+            hours: Some(Hours::parse("18-23").unwrap()),
+            tz: berlin,
+        };
+
+        // Saturday evenings and Sunday evenings in Berlin, within the window
+        assert!(evenings.is_active(at("2024-06-15T20:00")));
+        assert!(evenings.is_active(at("2024-07-14T18:30")));
+        assert!(!evenings.is_active(at("2024-06-15T12:00")));
+        assert!(!evenings.is_active(at("2024-06-17T20:00")));
+        assert!(!evenings.is_active(at("2024-07-20T20:00")));
+        assert!(!evenings.is_active(at("2024-06-08T20:00")));
+
+        assert!(!evenings.has_ended(at("2024-07-14")));
+        assert!(evenings.has_ended(at("2024-07-15")));
+
+        let always = ActiveTimes {
+            from: None,
+            until: None,
+            days: None,
+            hours: None,
+            tz: berlin,
+        };
+        assert!(always.is_active(at("1999-12-31")));
+        assert!(!always.has_ended(at("2999-12-31")));
+    }
+}
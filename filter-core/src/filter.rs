@@ -1,17 +1,24 @@
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time;
 use tracing::{debug, error, info};
 
-use crate::api::MinifluxClient;
+use crate::actions::{ActionHistory, ActionPlan, evaluate_with_full_content};
+use crate::api::{Entry, MinifluxClient};
+use crate::compiled::{CompiledRuleSet, RuleSetCache};
 use crate::config::Config;
-use crate::rules::{RuleSet, load_rule_sets_from_dir};
+use crate::rules::{RuleMatch, load_rule_sets_from_dir};
 
 pub struct FilterEngine {
     client: MinifluxClient,
     rules_dir: String,
     poll_interval: Duration,
+    history: Arc<ActionHistory>,
+    rule_stats: Arc<RuleStats>,
+    rule_sets: RuleSetCache,
 }
 
 impl FilterEngine {
@@ -20,9 +27,23 @@ impl FilterEngine {
             client: MinifluxClient::new(config),
             rules_dir,
             poll_interval: Duration::from_secs(config.poll_interval),
+            history: Arc::new(ActionHistory::default()),
+            rule_stats: Arc::new(RuleStats::default()),
+            rule_sets: RuleSetCache::default(),
         }
     }
 
+    /// History of performed actions, shared with the web UI so manual runs
+    /// do not repeat webhooks sent by the engine
+    pub fn history(&self) -> Arc<ActionHistory> {
+        self.history.clone()
+    }
+
+    /// Match counts of the rules, shared with the web UI
+    pub fn rule_stats(&self) -> Arc<RuleStats> {
+        self.rule_stats.clone()
+    }
+
     /// Start the main filtering loop
     pub async fn run(&self) -> Result<()> {
         info!(
@@ -51,41 +72,60 @@ impl FilterEngine {
     async fn process_cycle(&self) -> Result<()> {
         debug!("Starting new filtering cycle");
 
-        // Load rule sets
-        let rule_sets =
-            load_rule_sets_from_dir(&self.rules_dir).context("Failed to load rule sets")?;
+        // Load rule sets, only compiling files that changed since the last cycle
+        let rule_sets = self
+            .rule_sets
+            .load(&self.rules_dir)
+            .context("Failed to load rule sets")?;
 
         if rule_sets.is_empty() {
             debug!("No rule sets found, skipping cycle");
             return Ok(());
         }
 
-        // Group rule sets by feed ID for efficient processing
-        let rules_by_feed: HashMap<u64, &RuleSet> =
-            rule_sets.iter().map(|rs| (rs.feed_id, rs)).collect();
-
-        info!(
-            "Processing {} rule sets for {} feeds",
-            rule_sets.len(),
-            rules_by_feed.len()
-        );
+        let enabled_sets: Vec<&CompiledRuleSet> = rule_sets
+            .iter()
+            .map(|rs| rs.as_ref())
+            .filter(|rs| rs.rule_set().is_enabled())
+            .collect();
+
+        info!("Processing {} enabled rule sets", enabled_sets.len());
+
+        let entries = self.fetch_entries(&enabled_sets).await?;
+
+        // Entries that were read or removed since the last cycle will not
+        // match again, their history is no longer needed
+        let unread: HashSet<u64> = entries.iter().map(|entry| entry.id).collect();
+        self.history.retain_entries(&unread);
+
+        // Group entries by feed, so each feed is evaluated against the feed,
+        // category and global rule sets that apply to it
+        let mut entries_by_feed: BTreeMap<u64, Vec<Entry>> = BTreeMap::new();
+        for entry in entries {
+            entries_by_feed
+                .entry(entry.feed.id)
+                .or_default()
+                .push(entry);
+        }
 
-        // Process feeds with specific rules first, then all unread entries for general rules
-        let mut processed_feeds = std::collections::HashSet::new();
         let mut total_processed = 0;
         let mut total_filtered = 0;
 
-        // Process feeds with specific rules
-        for (&feed_id, rule_set) in &rules_by_feed {
-            if !rule_set.is_enabled() {
-                debug!("Skipping disabled rule set for feed {}", feed_id);
+        for (feed_id, entries) in entries_by_feed {
+            let feed = &entries[0].feed;
+            let feed_sets: Vec<&CompiledRuleSet> = enabled_sets
+                .iter()
+                .copied()
+                .filter(|rs| rs.rule_set().applies_to_feed(feed))
+                .collect();
+
+            if feed_sets.is_empty() {
                 continue;
             }
 
-            let (processed, filtered) = self.process_feed(feed_id, rule_set).await?;
+            let (processed, filtered) = self.process_feed(feed_id, entries, &feed_sets).await?;
             total_processed += processed;
             total_filtered += filtered;
-            processed_feeds.insert(feed_id);
         }
 
         info!(
@@ -96,60 +136,105 @@ impl FilterEngine {
         Ok(())
     }
 
-    /// Process entries for a specific feed with its rule set
-    async fn process_feed(&self, feed_id: u64, rule_set: &RuleSet) -> Result<(usize, usize)> {
+    /// Fetch the unread entries the given rule sets could apply to
+    async fn fetch_entries(&self, rule_sets: &[&CompiledRuleSet]) -> Result<Vec<Entry>> {
+        // Global rules need every unread entry, so fetch them all at once
+        // and reuse the result for the feed and category rule sets as well
+        if rule_sets.iter().any(|rs| rs.rule_set().is_global()) {
+            return self
+                .client
+                .get_unread_entries()
+                .await
+                .context("Failed to fetch unread entries");
+        }
+
+        let feed_ids: BTreeSet<u64> = rule_sets
+            .iter()
+            .filter_map(|rs| rs.rule_set().feed_id)
+            .collect();
+        let category_ids: BTreeSet<u64> = rule_sets
+            .iter()
+            .filter_map(|rs| rs.rule_set().category_id)
+            .collect();
+
+        let mut entries = Vec::new();
+
+        for feed_id in feed_ids {
+            let feed_entries = self
+                .client
+                .get_unread_entries_for_feed(feed_id)
+                .await
+                .with_context(|| format!("Failed to fetch entries for feed {}", feed_id))?;
+            entries.extend(feed_entries);
+        }
+
+        for category_id in category_ids {
+            let category_entries = self
+                .client
+                .get_unread_entries_for_category(category_id)
+                .await
+                .with_context(|| format!("Failed to fetch entries for category {}", category_id))?;
+            entries.extend(category_entries);
+        }
+
+        // A feed with its own rule set may also belong to a category with one
+        let mut seen = HashSet::new();
+        entries.retain(|entry| seen.insert(entry.id));
+
+        Ok(entries)
+    }
+
+    /// Process the unread entries of a feed with the rule sets that apply to it
+    async fn process_feed(
+        &self,
+        feed_id: u64,
+        mut entries: Vec<Entry>,
+        rule_sets: &[&CompiledRuleSet],
+    ) -> Result<(usize, usize)> {
         debug!(
-            "Processing feed {} with {} rules",
+            "Processing feed {} with {} rule sets",
             feed_id,
-            rule_set.rules.len()
+            rule_sets.len()
         );
 
-        // Fetch unread entries for this feed
-        let entries = self
-            .client
-            .get_unread_entries_for_feed(feed_id)
-            .await
-            .with_context(|| format!("Failed to fetch entries for feed {}", feed_id))?;
-
         if entries.is_empty() {
             debug!("No unread entries for feed {}", feed_id);
             return Ok((0, 0));
         }
 
-        let mut entries_to_mark = Vec::new();
+        let mut plan = ActionPlan::with_history(self.history.clone());
 
-        // Evaluate each entry against the rule set
-        for entry in &entries {
-            let matching_rules = rule_set.evaluate(entry);
+        // Evaluate each entry against the rule sets
+        for entry in &mut entries {
+            let matching_rules =
+                evaluate_with_full_content(&self.client, &self.history, rule_sets, entry).await;
 
             if !matching_rules.is_empty() {
-                let rule_indices: Vec<String> =
-                    matching_rules.iter().map(|i| (i + 1).to_string()).collect();
+                let rule_labels: Vec<String> =
+                    matching_rules.iter().map(|m| m.to_string()).collect();
                 info!(
                     "Entry '{}' (ID: {}) matches rules: {}",
                     entry.title,
                     entry.id,
-                    rule_indices.join(", ")
+                    rule_labels.join(", ")
                 );
-                entries_to_mark.push(entry.id);
+                log_scores(entry, rule_sets, &matching_rules);
             }
+
+            self.rule_stats.record(&matching_rules);
+            plan.add(entry, &matching_rules);
         }
 
-        // Mark matching entries as read
-        if !entries_to_mark.is_empty() {
-            self.client
-                .mark_entries_as_read(entries_to_mark.clone())
+        // Apply the actions of the matching rules
+        if !plan.is_empty() {
+            plan.execute(&self.client)
                 .await
-                .with_context(|| format!("Failed to mark entries as read for feed {}", feed_id))?;
+                .with_context(|| format!("Failed to apply actions for feed {}", feed_id))?;
 
-            info!(
-                "Marked {} entries as read for feed {}",
-                entries_to_mark.len(),
-                feed_id
-            );
+            info!("Feed {}: {}", feed_id, plan.summary());
         }
 
-        Ok((entries.len(), entries_to_mark.len()))
+        Ok((entries.len(), plan.matched()))
     }
 
     /// Get summary statistics for the current rule sets
@@ -158,24 +243,53 @@ impl FilterEngine {
 
         let total_rule_sets = rule_sets.len();
         let enabled_rule_sets = rule_sets.iter().filter(|rs| rs.is_enabled()).count();
+        let global_rule_sets = rule_sets.iter().filter(|rs| rs.is_global()).count();
+        let category_rule_sets = rule_sets
+            .iter()
+            .filter(|rs| rs.category_id.is_some())
+            .count();
         let total_rules = rule_sets.iter().map(|rs| rs.rules.len()).sum();
 
         // Get feed IDs that have rules
-        let feeds_with_rules: Vec<u64> = rule_sets.iter().map(|rs| rs.feed_id).collect();
+        let feeds_with_rules: Vec<u64> = rule_sets.iter().filter_map(|rs| rs.feed_id).collect();
 
         Ok(FilterStats {
             total_rule_sets,
             enabled_rule_sets,
+            global_rule_sets,
+            category_rule_sets,
             total_rules,
             feeds_with_rules,
         })
     }
 }
 
+/// Log which rules added up to the score of an entry that reached the
+/// threshold of a rule set. Scores below the threshold are only logged at
+/// debug level, by [`evaluate_rule_sets`](crate::compiled::evaluate_rule_sets).
+fn log_scores(entry: &Entry, rule_sets: &[&CompiledRuleSet], matches: &[RuleMatch]) {
+    for rule_match in matches.iter().filter(|m| m.is_score_threshold()) {
+        let card = rule_sets
+            .iter()
+            .find(|compiled| std::ptr::eq(compiled.rule_set(), rule_match.rule_set))
+            .and_then(|compiled| compiled.score(entry));
+        if let Some(card) = card {
+            info!(
+                "Entry {} scored {} in {} rule set",
+                entry.id,
+                card,
+                rule_match.rule_set.scope()
+            );
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FilterStats {
     pub total_rule_sets: usize,
     pub enabled_rule_sets: usize,
+    pub global_rule_sets: usize,
+    pub category_rule_sets: usize,
     pub total_rules: usize,
     pub feeds_with_rules: Vec<u64>,
 }
@@ -183,16 +297,63 @@ pub struct FilterStats {
 impl FilterStats {
     pub fn print_summary(&self) {
         info!(
-            "Filter Engine Statistics:\n  Total rule sets: {}\n  Enabled rule sets: {}\n  Total rules: {}\n  Feeds with rules: {:?}",
-            self.total_rule_sets, self.enabled_rule_sets, self.total_rules, self.feeds_with_rules
+            "Filter Engine Statistics:\n  Total rule sets: {}\n  Enabled rule sets: {}\n  Global rule sets: {}\n  Category rule sets: {}\n  Total rules: {}\n  Feeds with rules: {:?}",
+            self.total_rule_sets,
+            self.enabled_rule_sets,
+            self.global_rule_sets,
+            self.category_rule_sets,
+            self.total_rules,
+            self.feeds_with_rules
         );
     }
 }
 
+/// How often each rule matched since the filter started. Rules are counted
+/// by rule set and ID, so the counts survive reordering rules with IDs.
+#[derive(Debug, Default)]
+pub struct RuleStats {
+    counts: Mutex<HashMap<(String, String), RuleMatchCount>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatchCount {
+    /// Label of the rule as of its latest match, e.g. "global rule spam (Spam)"
+    pub rule: String,
+    pub description: Option<String>,
+    pub matches: usize,
+}
+
+impl RuleStats {
+    pub fn record(&self, matches: &[RuleMatch]) {
+        let mut counts = self.counts.lock().unwrap();
+        for rule_match in matches {
+            let rule = rule_match.rule();
+            let key = (rule_match.rule_set.scope(), rule.key(rule_match.index));
+            let count = counts.entry(key).or_insert_with(|| RuleMatchCount {
+                rule: String::new(),
+                description: None,
+                matches: 0,
+            });
+            count.rule = rule_match.to_string();
+            count.description = rule.description.clone();
+            count.matches += 1;
+        }
+    }
+
+    /// Match counts of the rules that matched at least once, most matches first
+    pub fn counts(&self) -> Vec<RuleMatchCount> {
+        let mut counts: Vec<RuleMatchCount> =
+            self.counts.lock().unwrap().values().cloned().collect();
+        counts.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.rule.cmp(&b.rule)));
+        counts
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::rules::{Rule, RuleSet};
 
     #[test]
     fn test_filter_engine_creation() {
@@ -208,4 +369,44 @@ mod tests {
         assert_eq!(engine.poll_interval, Duration::from_secs(300));
         assert_eq!(engine.rules_dir, "./rules");
     }
+
+    #[test]
+    fn test_rule_stats_follow_rule_ids() {
+        let rule = |id: &str| Rule {
+            id: Some(id.to_string()),
+            ..Default::default()
+        };
+        let mut rule_set = RuleSet {
+            global: true,
+            rules: vec![rule("spam"), rule("ads")],
+            ..Default::default()
+        };
+
+        let stats = RuleStats::default();
+        let matches = |rule_set: &RuleSet, indices: &[usize]| {
+            let matches: Vec<RuleMatch> = indices
+                .iter()
+                .map(|&index| RuleMatch::new(rule_set, index))
+                .collect();
+            stats.record(&matches);
+        };
+        matches(&rule_set, &[0, 1]);
+        // Reordering and renaming keeps counting the same rule
+        rule_set.rules.reverse();
+        rule_set.rules[1].name = Some("Spam".to_string());
+        matches(&rule_set, &[1]);
+
+        let counts: Vec<_> = stats
+            .counts()
+            .into_iter()
+            .map(|count| (count.rule, count.matches))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("global rule spam (Spam)".to_string(), 2),
+                ("global rule ads".to_string(), 1)
+            ]
+        );
+    }
 }
@@ -0,0 +1,83 @@
+use aho_corasick::AhoCorasick;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+/// Collects the needles of substring conditions that search the same text,
+/// so that all of them are found in a single pass
+#[derive(Debug, Default)]
+pub struct NeedleSetBuilder {
+    needles: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+impl NeedleSetBuilder {
+    /// Add a needle and return its id, the same needle always gets the same id
+    pub fn add(&mut self, needle: String) -> usize {
+        if let Some(&id) = self.ids.get(&needle) {
+            return id;
+        }
+        let id = self.needles.len();
+        self.ids.insert(needle.clone(), id);
+        self.needles.push(needle);
+        id
+    }
+
+    pub fn build(self) -> Result<NeedleSet> {
+        let automaton =
+            AhoCorasick::new(&self.needles).context("Failed to build substring automaton")?;
+        Ok(NeedleSet {
+            automaton,
+            len: self.needles.len(),
+        })
+    }
+}
+
+/// An Aho-Corasick automaton over the needles of many conditions
+#[derive(Debug)]
+pub struct NeedleSet {
+    automaton: AhoCorasick,
+    len: usize,
+}
+
+impl NeedleSet {
+    /// Which needles occur in the haystack, indexed by needle id
+    pub fn find_all(&self, haystack: &str) -> Vec<bool> {
+        let mut found = vec![false; self.len];
+        let mut remaining = self.len;
+
+        for m in self.automaton.find_overlapping_iter(haystack) {
+            let id = m.pattern().as_usize();
+            if !found[id] {
+                found[id] = true;
+                remaining -= 1;
+                if remaining == 0 {
+                    break;
+                }
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all_overlapping_needles() {
+        let mut builder = NeedleSetBuilder::default();
+        let ids: Vec<usize> = ["crypto", "cryptocurrency", "currency", "token", "crypto"]
+            .into_iter()
+            .map(|needle| builder.add(needle.to_string()))
+            .collect();
+        assert_eq!(ids, vec![0, 1, 2, 3, 0]);
+
+        let needles = builder.build().unwrap();
+        assert_eq!(
+            needles.find_all("buy cryptocurrency now"),
+            vec![true, true, true, false]
+        );
+        assert_eq!(needles.find_all(""), vec![false; 4]);
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Result, anyhow};
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use std::sync::LazyLock;
+
+/// Elements whose content is not text a reader sees
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "template", "head"];
+
+/// Elements that do not separate words, e.g. `<b>Rust</b>acean` is one word
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "del", "dfn", "em", "font", "i", "ins",
+    "kbd", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// The text of an element as a reader sees it: tags are stripped, entities
+/// decoded and whitespace collapsed
+pub fn element_text(element: ElementRef) -> String {
+    let mut text = String::new();
+    push_text(element, &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn push_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        if let Some(child_text) = child.value().as_text() {
+            text.push_str(child_text);
+        } else if let Some(child) = ElementRef::wrap(child) {
+            let name = child.value().name();
+            if HIDDEN_ELEMENTS.contains(&name) {
+                continue;
+            }
+            let separates = !INLINE_ELEMENTS.contains(&name);
+            if separates {
+                text.push(' ');
+            }
+            push_text(child, text);
+            if separates {
+                text.push(' ');
+            }
+        }
+    }
+}
+
+/// Domains of the absolute http(s) links in an HTML fragment, without
+/// duplicates and in order of appearance
+pub fn link_domains(fragment: &Html) -> Vec<String> {
+    static LINK: LazyLock<Selector> = LazyLock::new(|| Selector::parse("a[href]").unwrap());
+
+    let mut domains = Vec::new();
+    for link in fragment.select(&LINK) {
+        if let Some(domain) = link.value().attr("href").and_then(url_domain)
+            && !domains.contains(&domain)
+        {
+            domains.push(domain);
+        }
+    }
+    domains
+}
+
+/// Domain of an absolute http(s) URL
+pub fn url_domain(url: &str) -> Option<String> {
+    let url = Url::parse(url.trim()).ok()?;
+    match url.scheme() {
+        "http" | "https" => url.host_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// The text, or the value of `attribute`, of every element matching a
+/// selector. Elements without the attribute are left out.
+pub fn select(fragment: &Html, selector: &Selector, attribute: Option<&str>) -> Vec<String> {
+    fragment
+        .select(selector)
+        .filter_map(|element| match attribute {
+            Some(attribute) => element.value().attr(attribute).map(str::to_string),
+            None => Some(element_text(element)),
+        })
+        .collect()
+}
+
+/// Parse a CSS selector from a rule
+pub fn parse_selector(value: &str) -> Result<Selector> {
+    Selector::parse(value).map_err(|e| anyhow!("Invalid CSS selector '{}': {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_text() {
+        let html = r#"<p>Tom &amp; Jerry <b>re</b>turn</p><script>track("script")</script>
+            <ul><li>One</li><li>Two&nbsp;three</li></ul><img src="script.png">"#;
+        let fragment = Html::parse_fragment(html);
+        assert_eq!(
+            element_text(fragment.root_element()),
+            "Tom & Jerry return One Two three"
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let fragment = Html::parse_fragment(
+            r#"<h2>First <i>one</i></h2><p><a href="/a">a</a><a>b</a></p><h2>Second</h2>"#,
+        );
+        let heading = parse_selector("h2:first-of-type").unwrap();
+        assert_eq!(select(&fragment, &heading, None), vec!["First one"]);
+        let links = parse_selector("a").unwrap();
+        assert_eq!(select(&fragment, &links, Some("href")), vec!["/a"]);
+        assert!(parse_selector("h2[").is_err());
+    }
+
+    #[test]
+    fn test_link_domains() {
+        let html = r#"<a href="https://Example.com/a">a</a> <a href="/local">b</a>
+            <a href="mailto:me@example.org">c</a> <a href="http://example.com/b">d</a>
+            <a href=" https://news.ycombinator.com/item?id=1 ">e</a>"#;
+        assert_eq!(
+            link_domains(&Html::parse_fragment(html)),
+            vec!["example.com", "news.ycombinator.com"]
+        );
+    }
+}
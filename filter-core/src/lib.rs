@@ -1,8 +1,16 @@
 //! Filter Core - Core filtering functionality for Miniflux RSS reader
 
+pub mod actions;
 pub mod api;
+pub mod compiled;
 pub mod config;
+pub mod dates;
 pub mod filter;
+pub mod fused;
+pub mod html;
+pub mod lists;
 pub mod rules;
+pub mod text;
+pub mod webhook;
 
 pub type Result<T> = anyhow::Result<T>;
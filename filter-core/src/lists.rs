@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Folder within the rules directory that holds keyword lists
+pub const LISTS_DIR: &str = "lists";
+
+/// Extension of keyword list files
+pub const LIST_EXTENSION: &str = "txt";
+
+/// A list of keywords shared by rules, read from `lists/<name>.txt` in the
+/// rules directory. Each line is a term, or a regex when written as
+/// `/pattern/`. Blank lines and lines starting with `#` are ignored.
+#[derive(Debug)]
+pub struct KeywordList {
+    name: String,
+    terms: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl KeywordList {
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        let mut patterns = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line
+                .strip_prefix('/')
+                .and_then(|line| line.strip_suffix('/'))
+            {
+                Some(pattern) if !pattern.is_empty() => {
+                    Regex::new(pattern).with_context(|| {
+                        format!(
+                            "Invalid regex on line {} of list '{}': '{}'",
+                            i + 1,
+                            name,
+                            pattern
+                        )
+                    })?;
+                    patterns.push(pattern.to_string());
+                }
+                _ => terms.push(line.to_string()),
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            terms,
+            patterns,
+        })
+    }
+
+    /// Load a list file, naming the list after the file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .with_context(|| format!("Invalid list file name: {}", path.display()))?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read list file: {}", path.display()))?;
+        Self::parse(name, &content)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Plain terms, matched as substrings
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Regex patterns
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+/// Keyword lists by name
+#[derive(Debug, Clone, Default)]
+pub struct KeywordLists(HashMap<String, Arc<KeywordList>>);
+
+impl KeywordLists {
+    pub fn get(&self, name: &str) -> Option<&Arc<KeywordList>> {
+        self.0.get(name)
+    }
+
+    pub fn insert(&mut self, list: Arc<KeywordList>) {
+        self.0.insert(list.name().to_string(), list);
+    }
+}
+
+/// Check that a list name can be used as a file name within the lists folder
+pub fn validate_list_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid list name '{}', use letters, digits, '-' and '_' only",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let list = KeywordList::parse(
+            "spam",
+            "# Spam words\ncasino\n\n  free money  \n/crypto ?(airdrop|giveaway)/\n/\n",
+        )
+        .unwrap();
+        assert_eq!(list.terms(), ["casino", "free money", "/"]);
+        assert_eq!(list.patterns(), ["crypto ?(airdrop|giveaway)"]);
+
+        assert!(KeywordList::parse("spam", "/[unclosed/").is_err());
+        assert!(validate_list_name("spam-words_2").is_ok());
+        assert!(validate_list_name("../secrets").is_err());
+    }
+}
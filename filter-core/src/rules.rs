@@ -1,50 +1,423 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
-use crate::api::Entry;
+use crate::api::Feed;
+use crate::compiled::check_condition;
+use crate::dates::{ActiveTimes, Hours, parse_timestamp, parse_timezone, parse_weekdays};
+use crate::webhook::MAX_RETRIES;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct RuleSet {
-    pub feed_id: u64,
+    /// Feed this rule set applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_id: Option<u64>,
+    /// Category whose feeds this rule set applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<u64>,
+    /// Apply this rule set to the unread entries of every feed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub global: bool,
+    /// Feeds a global rule set does not apply to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_feeds: Vec<u64>,
+    /// Defaults to enabled, so `{"feed_id": 123}` creates a working rule set
+    #[serde(default = "enabled_by_default")]
     pub enabled: bool,
+    /// Fetch the original article of every entry before evaluating, for
+    /// feeds that only ship a summary
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fetch_content: bool,
+    /// Timezone for date conditions, e.g. "Europe/Berlin". Defaults to UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// When the rules of this rule set are active
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Add up the scores of the scored rules an entry matches, and act once
+    /// the total reaches a threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoring: Option<Scoring>,
+    #[serde(default)]
     pub rules: Vec<Rule>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Rule {
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Scoring mode of a rule set
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Scoring {
+    /// Total score at which the action applies
+    pub threshold: i32,
+    #[serde(default)]
     pub action: Action,
-    pub conditions: Vec<Condition>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// ID of the rule standing for the score threshold of a scoring rule set
+pub const SCORE_RULE_ID: &str = "score";
+
+impl Scoring {
+    /// The threshold as a rule, so it is ordered, logged and counted like
+    /// the other rules of the rule set
+    pub fn rule(&self) -> Rule {
+        Rule {
+            id: Some(SCORE_RULE_ID.to_string()),
+            name: Some(format!("score of {} or more", self.threshold)),
+            action: Some(self.action.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Rule {
+    /// Stable identifier used in logs and stats, unique within the rule set.
+    /// The web editor assigns one to rules that have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Human readable name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What the rule is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Required, except on scored rules, which do not perform an action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+    /// Points added to the entry's score when the rule matches, in a rule
+    /// set with `scoring`. Scored rules do not perform their action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
+    /// Rules with a higher priority are evaluated first, across all rule
+    /// sets that apply to an entry. Rules of equal priority keep their order.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    /// Skip all later rules once this one matches
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stop: bool,
+    /// When the rule is active, within the schedule of its rule set
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Top-level conditions, all of which must match
+    pub conditions: Vec<ConditionNode>,
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+/// When a rule or rule set is active, e.g. to mute a topic for the duration
+/// of an event. Times are interpreted in the rule set's timezone.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Schedule {
+    /// Start of the active window, e.g. "2024-06-14" or "2024-06-14T18:00"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<String>,
+    /// End of the active window, after which the rule has expired
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<String>,
+    /// Days of the week the rule is active, e.g. "sat,sun"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_days: Option<String>,
+    /// Hours of the day the rule is active, e.g. "18-23"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_hours: Option<String>,
+}
+
+impl Schedule {
+    pub fn parse(&self, tz: Tz) -> Result<ActiveTimes> {
+        let from = self
+            .active_from
+            .as_deref()
+            .map(|value| parse_timestamp(value, tz))
+            .transpose()
+            .context("Invalid active_from")?;
+        let until = self
+            .active_until
+            .as_deref()
+            .map(|value| parse_timestamp(value, tz))
+            .transpose()
+            .context("Invalid active_until")?;
+        if let (Some(from), Some(until)) = (from, until)
+            && from >= until
+        {
+            anyhow::bail!("active_from must be before active_until");
+        }
+
+        Ok(ActiveTimes {
+            from,
+            until,
+            days: self
+                .active_days
+                .as_deref()
+                .map(parse_weekdays)
+                .transpose()
+                .context("Invalid active_days")?,
+            hours: self
+                .active_hours
+                .as_deref()
+                .map(Hours::parse)
+                .transpose()
+                .context("Invalid active_hours")?,
+            tz,
+        })
+    }
+
+    /// Whether the active window is over. Invalid schedules are left to
+    /// [`RuleSet::validate`].
+    fn has_ended(&self, tz: Tz, now: DateTime<Utc>) -> bool {
+        self.parse(tz).is_ok_and(|times| times.has_ended(now))
+    }
+}
+
+impl Rule {
+    /// The rule's ID, or its position in the rule set when it has none
+    pub fn key(&self, index: usize) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => (index + 1).to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Action {
+    #[default]
     MarkRead,
+    /// Set the entry to Miniflux's "removed" status, hiding it everywhere
+    Remove,
+    /// Star the entry in Miniflux, leaving it unread
+    Bookmark,
+    /// Replace regex matches in the entry title, `$1` etc. refer to capture groups
+    RewriteTitle {
+        pattern: String,
+        replacement: String,
+    },
+    /// Replace regex matches in the entry content
+    RewriteContent {
+        pattern: String,
+        replacement: String,
+    },
+    /// Fetch the original article and evaluate the entry again against the
+    /// full content, so content conditions work on truncated feeds
+    FetchContent,
+    /// Send the entry to the integrations configured in Miniflux, e.g. a
+    /// read-later service
+    Save,
+    /// Exempt the entry from the actions of all later rules, e.g. to never
+    /// hide posts by a certain author
+    Keep,
+    /// POST the entry, its feed and the matched rule as JSON to a URL
+    Webhook {
+        url: String,
+        /// Signs the body with HMAC-SHA256 when set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+        /// Number of retries after a failed delivery
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retries: Option<u32>,
+    },
+}
+
+/// A node in a rule's condition tree: either a single condition or a
+/// group combining child nodes. Plain conditions keep the flat format of
+/// existing rule files, groups are written as `all = [...]`, `any = [...]`,
+/// `not = {...}` or `at_least = 2, of = [...]`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ConditionNode {
+    /// Matches when every child matches
+    All {
+        all: Vec<ConditionNode>,
+    },
+    /// Matches when at least one child matches
+    Any {
+        any: Vec<ConditionNode>,
+    },
+    /// Matches when the child does not match
+    Not {
+        not: Box<ConditionNode>,
+    },
+    /// Matches when at least `at_least` of the children match
+    AtLeast {
+        at_least: usize,
+        of: Vec<ConditionNode>,
+    },
+    Condition(Condition),
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl<'de> Deserialize<'de> for ConditionNode {
+    /// Dispatch on the group keys rather than trying every variant like an
+    /// untagged enum would, so a typo in a plain condition reports the field
+    /// or operator at fault
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+
+        fn take<T: DeserializeOwned, E: de::Error>(
+            value: &mut serde_json::Value,
+            key: &'static str,
+        ) -> Result<T, E> {
+            let child = value
+                .get_mut(key)
+                .ok_or_else(|| E::missing_field(key))?
+                .take();
+            serde_json::from_value(child).map_err(|e| E::custom(format!("{}: {}", key, e)))
+        }
+
+        Ok(if value.get("all").is_some() {
+            ConditionNode::All {
+                all: take(&mut value, "all")?,
+            }
+        } else if value.get("any").is_some() {
+            ConditionNode::Any {
+                any: take(&mut value, "any")?,
+            }
+        } else if value.get("not").is_some() {
+            ConditionNode::Not {
+                not: take(&mut value, "not")?,
+            }
+        } else if value.get("at_least").is_some() {
+            ConditionNode::AtLeast {
+                at_least: take(&mut value, "at_least")?,
+                of: take(&mut value, "of")?,
+            }
+        } else {
+            ConditionNode::Condition(serde_json::from_value(value).map_err(de::Error::custom)?)
+        })
+    }
+}
+
+impl From<Condition> for ConditionNode {
+    fn from(condition: Condition) -> Self {
+        ConditionNode::Condition(condition)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Condition {
     pub field: Field,
     pub operator: Operator,
     pub value: String,
+    /// Compare case-sensitively. Defaults to case-insensitive for every
+    /// operator except `matches`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    /// Ignore accents and other diacritics, so "cafe" matches "café"
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_accents: bool,
+    /// How many words apart the words of a `near` condition may be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<usize>,
+    /// CSS selector that narrows `content` down to the matching elements,
+    /// e.g. "h2:first-of-type"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Compare this attribute of the selected elements instead of their text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Field {
+    #[default]
     Title,
+    /// Text of the content, without HTML tags and with entities decoded
     Content,
+    /// Content as HTML, tags and attributes included
+    RawContent,
     Author,
     Url,
     Tag,
+    /// Domains the links in the content point to
+    LinkDomain,
+    /// Publication date of the entry
+    Published,
+    /// Date Miniflux fetched the entry
+    Created,
+    /// Estimated reading time in minutes, as computed by Miniflux
+    ReadingTime,
+    /// Number of words in the content
+    WordCount,
+    /// Number of characters in the content
+    ContentLength,
+    /// URLs of the attached media files
+    EnclosureUrl,
+    /// MIME types of the attached media files, e.g. "audio/mpeg"
+    EnclosureType,
+    /// Sizes of the attached media files in bytes
+    EnclosureSize,
+    /// Number of attached media files
+    EnclosureCount,
+    /// Title of the feed the entry belongs to
+    FeedTitle,
+    /// URL of the feed itself
+    FeedUrl,
+    /// Domain of the feed's website, e.g. "blog.rust-lang.org"
+    SiteDomain,
+    /// Title of the feed's category
+    CategoryTitle,
+}
+
+impl Field {
+    /// The kind of value this field holds, which decides the operators it takes
+    pub fn kind(&self) -> FieldKind {
+        match self {
+            Field::Title
+            | Field::Content
+            | Field::RawContent
+            | Field::Author
+            | Field::Url
+            | Field::Tag
+            | Field::LinkDomain
+            | Field::EnclosureUrl
+            | Field::EnclosureType
+            | Field::FeedTitle
+            | Field::FeedUrl
+            | Field::SiteDomain
+            | Field::CategoryTitle => FieldKind::Text,
+            Field::Published | Field::Created => FieldKind::Date,
+            Field::ReadingTime
+            | Field::WordCount
+            | Field::ContentLength
+            | Field::EnclosureSize
+            | Field::EnclosureCount => FieldKind::Number,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Date,
+    Number,
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldKind::Text => "text",
+            FieldKind::Date => "date",
+            FieldKind::Number => "numeric",
+        })
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Operator {
+    #[default]
     Contains,
     NotContains,
     Equals,
@@ -52,10 +425,63 @@ pub enum Operator {
     StartsWith,
     EndsWith,
     Matches, // For regex
+    /// Text contains the value as a whole word or phrase, so "ai" does not
+    /// match "maintain"
+    ContainsWord,
+    /// Text contains all words of the value at most `distance` words apart
+    Near,
+    /// Text contains a term of, or matches a regex in, the keyword list
+    /// named by the value
+    InList,
+    /// Date is more than a duration like "2d" in the past
+    OlderThan,
+    /// Date is less than a duration in the past
+    NewerThan,
+    /// Date is before a timestamp like "2024-07-20" or "2024-07-20T18:00"
+    Before,
+    /// Date is at or after a timestamp
+    After,
+    /// Date falls on one of a comma separated list of days, e.g. "sat,sun"
+    DayOfWeek,
+    /// Date falls within hours like "9-17" or "22-6"
+    HourOfDay,
+    /// Number is greater than the value
+    GreaterThan,
+    /// Number is less than the value
+    LessThan,
+    /// Number is within an inclusive range like "10-20"
+    Between,
+}
+
+impl Operator {
+    /// Whether this operator can be used on fields of the given kind
+    pub fn supports(&self, kind: FieldKind) -> bool {
+        match self {
+            Operator::Contains
+            | Operator::NotContains
+            | Operator::StartsWith
+            | Operator::EndsWith
+            | Operator::Matches
+            | Operator::ContainsWord
+            | Operator::Near
+            | Operator::InList => kind == FieldKind::Text,
+            Operator::Equals | Operator::NotEquals => kind != FieldKind::Date,
+            Operator::OlderThan
+            | Operator::NewerThan
+            | Operator::Before
+            | Operator::After
+            | Operator::DayOfWeek
+            | Operator::HourOfDay => kind == FieldKind::Date,
+            Operator::GreaterThan | Operator::LessThan | Operator::Between => {
+                kind == FieldKind::Number
+            }
+        }
+    }
 }
 
 impl RuleSet {
-    /// Load a rule set from a TOML file
+    /// Load a rule set from a TOML file. Rules without an ID get one from
+    /// their position in the file, see [`RuleSet::assign_rule_ids`].
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
         debug!("Loading rule set from {}", path.display());
@@ -63,14 +489,15 @@ impl RuleSet {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read rule file: {}", path.display()))?;
 
-        let rule_set: RuleSet = toml::from_str(&content)
+        let mut rule_set: RuleSet = toml::from_str(&content)
             .with_context(|| format!("Failed to parse TOML rule file: {}", path.display()))?;
 
         rule_set.validate()?;
+        rule_set.assign_rule_ids();
 
         debug!(
-            "Loaded rule set for feed {} with {} rules",
-            rule_set.feed_id,
+            "Loaded {} rule set with {} rules",
+            rule_set.scope(),
             rule_set.rules.len()
         );
 
@@ -96,147 +523,417 @@ impl RuleSet {
         fs::write(path, content)
             .with_context(|| format!("Failed to write rule file: {}", path.display()))?;
 
-        info!(
-            "Saved rule set for feed {} to {}",
-            self.feed_id,
-            path.display()
-        );
+        info!("Saved {} rule set to {}", self.scope(), path.display());
 
         Ok(())
     }
 
     /// Validate the rule set
     pub fn validate(&self) -> Result<()> {
+        let scopes = [
+            self.feed_id.is_some(),
+            self.category_id.is_some(),
+            self.global,
+        ];
+        if scopes.iter().filter(|&&set| set).count() != 1 {
+            anyhow::bail!(
+                "Rule set must have exactly one of feed_id, category_id or global = true"
+            );
+        }
+
+        if !self.global && !self.exclude_feeds.is_empty() {
+            anyhow::bail!("exclude_feeds is only supported for global rule sets");
+        }
+
+        let tz = parse_timezone(self.timezone.as_deref())?;
+
         if self.rules.is_empty() {
-            warn!("Rule set for feed {} has no rules", self.feed_id);
+            warn!("The {} rule set has no rules", self.scope());
+        }
+
+        let now = Utc::now();
+        if self.schedule.parse(tz)?.has_ended(now) {
+            warn!(
+                "The {} rule set expired on {}",
+                self.scope(),
+                self.schedule.active_until.as_deref().unwrap_or_default()
+            );
+        }
+
+        if let Some(scoring) = &self.scoring {
+            if !self.rules.iter().any(|rule| rule.score.is_some()) {
+                anyhow::bail!("A rule set with scoring needs rules with a score");
+            }
+            validate_action(&scoring.action, "scoring")?;
         }
 
+        let mut ids = HashSet::new();
         for (i, rule) in self.rules.iter().enumerate() {
+            if let Some(id) = &rule.id {
+                validate_rule_id(id)?;
+                if !ids.insert(id) {
+                    anyhow::bail!("Rule ID '{}' is used by more than one rule", id);
+                }
+                if self.scoring.is_some() && id == SCORE_RULE_ID {
+                    anyhow::bail!(
+                        "Rule ID '{}' is reserved for the score threshold",
+                        SCORE_RULE_ID
+                    );
+                }
+            }
+
+            if rule.score.is_some() && self.scoring.is_none() {
+                anyhow::bail!(
+                    "Rule {} has a score, but the rule set has no scoring threshold",
+                    i + 1
+                );
+            }
+
             if rule.conditions.is_empty() {
                 anyhow::bail!("Rule {} has no conditions", i + 1);
             }
 
-            for (j, condition) in rule.conditions.iter().enumerate() {
-                if condition.value.trim().is_empty() {
-                    anyhow::bail!("Rule {} condition {} has an empty value", i + 1, j + 1);
-                }
+            for (j, node) in rule.conditions.iter().enumerate() {
+                validate_node(node, tz, &format!("Rule {} condition {}", i + 1, j + 1))?;
+            }
 
-                // Validate regex patterns if using Matches operator
-                if let Operator::Matches = condition.operator {
-                    regex::Regex::new(&condition.value).with_context(|| {
-                        format!(
-                            "Invalid regex pattern in rule {} condition {}: '{}'",
-                            i + 1,
-                            j + 1,
-                            condition.value
-                        )
-                    })?;
-                }
+            let times = rule
+                .schedule
+                .parse(tz)
+                .with_context(|| format!("Invalid schedule in rule {}", i + 1))?;
+            if times.has_ended(now) {
+                warn!(
+                    "Rule {} of the {} rule set expired on {}",
+                    rule.key(i),
+                    self.scope(),
+                    rule.schedule.active_until.as_deref().unwrap_or_default()
+                );
+            }
+
+            match (&rule.action, rule.score) {
+                (Some(action), None) => validate_action(action, &format!("rule {}", i + 1))?,
+                (None, None) => anyhow::bail!("Rule {} has no action", i + 1),
+                (Some(_), Some(_)) => anyhow::bail!(
+                    "Rule {} has a score, so it cannot have an action as well",
+                    i + 1
+                ),
+                (None, Some(_)) => {}
             }
         }
 
         Ok(())
     }
 
+    /// Evaluate the scored rules against an entry and return its score, or
+    /// `None` when the rule set has no scoring. Like [`RuleSet::evaluate`]
+    /// this compiles the rule set on every call, without keyword lists.
+    #[cfg(test)]
+    pub(crate) fn score(&self, entry: &crate::api::Entry) -> Option<crate::compiled::ScoreCard> {
+        crate::compiled::CompiledRuleSet::compile(self.clone())
+            .expect("Failed to compile rule set")
+            .score(entry)
+    }
+
+    /// Give every rule without an ID one derived from its name, or from its
+    /// position when it has no name, so it keeps its ID when rules are
+    /// reordered later. Done when loading a file, so that positions are the
+    /// ones in the file rather than those of a reordered copy being saved.
+    pub fn assign_rule_ids(&mut self) {
+        let mut taken: HashSet<String> = self.rules.iter().filter_map(|r| r.id.clone()).collect();
+        if self.scoring.is_some() {
+            taken.insert(SCORE_RULE_ID.to_string());
+        }
+
+        for (i, rule) in self.rules.iter_mut().enumerate() {
+            if rule.id.is_some() {
+                continue;
+            }
+
+            let base = rule
+                .name
+                .as_deref()
+                .map(slug)
+                .filter(|slug| !is_numeric(slug))
+                .unwrap_or_else(|| format!("rule-{}", i + 1));
+            let mut id = base.clone();
+            let mut n = 2;
+            while taken.contains(&id) {
+                id = format!("{}-{}", base, n);
+                n += 1;
+            }
+
+            taken.insert(id.clone());
+            rule.id = Some(id);
+        }
+    }
+
+    /// Whether the active window of the rule set is over
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.schedule.has_ended(self.tz(), now)
+    }
+
+    /// Indices of the rules whose active window is over
+    pub fn expired_rules(&self, now: DateTime<Utc>) -> Vec<usize> {
+        let tz = self.tz();
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.schedule.has_ended(tz, now))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn tz(&self) -> Tz {
+        parse_timezone(self.timezone.as_deref()).unwrap_or(Tz::UTC)
+    }
+
     /// Check if the rule set is enabled
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
-    /// Evaluate all rules against an entry and return matching rule indices
-    pub fn evaluate(&self, entry: &Entry) -> Vec<usize> {
-        if !self.is_enabled() {
-            return Vec::new();
+    /// Check if the rule set applies to every feed
+    pub fn is_global(&self) -> bool {
+        self.global
+    }
+
+    /// Check if the rule set applies to entries of the given feed
+    pub fn applies_to_feed(&self, feed: &Feed) -> bool {
+        if self.global {
+            !self.exclude_feeds.contains(&feed.id)
+        } else if let Some(category_id) = self.category_id {
+            feed.category.as_ref().map(|c| c.id) == Some(category_id)
+        } else {
+            self.feed_id == Some(feed.id)
         }
+    }
 
-        let mut matching_rules = Vec::new();
+    /// Short description of what the rule set applies to, for log messages
+    pub fn scope(&self) -> String {
+        match (self.feed_id, self.category_id) {
+            (Some(feed_id), _) => format!("feed {}", feed_id),
+            (None, Some(category_id)) => format!("category {}", category_id),
+            (None, None) => "global".to_string(),
+        }
+    }
 
-        for (i, rule) in self.rules.iter().enumerate() {
-            if self.evaluate_rule(rule, entry) {
-                debug!("Entry {} matches rule {}", entry.id, i + 1);
-                matching_rules.push(i);
+    /// Name of the file this rule set is stored in within the rules directory
+    pub fn file_name(&self) -> String {
+        match (self.feed_id, self.category_id) {
+            (Some(feed_id), _) => format!("feed_{}.toml", feed_id),
+            (None, Some(category_id)) => format!("category_{}.toml", category_id),
+            (None, None) => "global.toml".to_string(),
+        }
+    }
+
+    /// All conditions of all rules, including those nested in groups
+    pub fn conditions(&self) -> Vec<&Condition> {
+        fn collect<'a>(node: &'a ConditionNode, conditions: &mut Vec<&'a Condition>) {
+            match node {
+                ConditionNode::All { all: children }
+                | ConditionNode::Any { any: children }
+                | ConditionNode::AtLeast { of: children, .. } => {
+                    children.iter().for_each(|child| collect(child, conditions));
+                }
+                ConditionNode::Not { not } => collect(not, conditions),
+                ConditionNode::Condition(condition) => conditions.push(condition),
             }
         }
 
-        matching_rules
+        let mut conditions = Vec::new();
+        for rule in &self.rules {
+            for node in &rule.conditions {
+                collect(node, &mut conditions);
+            }
+        }
+        conditions
     }
 
-    /// Evaluate a single rule against an entry
-    fn evaluate_rule(&self, rule: &Rule, entry: &Entry) -> bool {
-        // All conditions must be true for the rule to match
-        rule.conditions
-            .iter()
-            .all(|condition| self.evaluate_condition(condition, entry))
-    }
-
-    /// Evaluate a single condition against an entry
-    fn evaluate_condition(&self, condition: &Condition, entry: &Entry) -> bool {
-        let field_value = match condition.field {
-            Field::Title => &entry.title,
-            Field::Content => &entry.content,
-            Field::Author => &entry.author,
-            Field::Url => &entry.url,
-            Field::Tag => {
-                // For tags, we check if any tag matches the condition
-                let tags_joined = entry.tags.join(" ");
-                return match condition.operator {
-                    Operator::Contains => tags_joined
-                        .to_lowercase()
-                        .contains(&condition.value.to_lowercase()),
-                    Operator::NotContains => !tags_joined
-                        .to_lowercase()
-                        .contains(&condition.value.to_lowercase()),
-                    Operator::Equals => entry
-                        .tags
-                        .iter()
-                        .any(|tag| tag.eq_ignore_ascii_case(&condition.value)),
-                    Operator::NotEquals => !entry
-                        .tags
-                        .iter()
-                        .any(|tag| tag.eq_ignore_ascii_case(&condition.value)),
-                    Operator::StartsWith => entry.tags.iter().any(|tag| {
-                        tag.to_lowercase()
-                            .starts_with(&condition.value.to_lowercase())
-                    }),
-                    Operator::EndsWith => entry.tags.iter().any(|tag| {
-                        tag.to_lowercase()
-                            .ends_with(&condition.value.to_lowercase())
-                    }),
-                    Operator::Matches => match regex::Regex::new(&condition.value) {
-                        Ok(re) => entry.tags.iter().any(|tag| re.is_match(tag)),
-                        Err(_) => {
-                            warn!("Invalid regex pattern '{}' in condition", condition.value);
-                            false
-                        }
-                    },
-                };
+    /// Evaluate all rules against an entry and return the indices of the
+    /// rules whose actions apply, in the order they were evaluated.
+    /// This compiles the rule set on every call and without keyword lists,
+    /// which only suits tests; the filter engine keeps
+    /// [`CompiledRuleSet`](crate::compiled::CompiledRuleSet)s
+    /// compiled with the lists of the rules directory instead.
+    #[cfg(test)]
+    pub(crate) fn evaluate(&self, entry: &crate::api::Entry) -> Vec<usize> {
+        crate::compiled::CompiledRuleSet::compile(self.clone())
+            .expect("Failed to compile rule set")
+            .evaluate(entry)
+    }
+}
+
+/// A rule that matched an entry, along with the rule set it belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct RuleMatch<'a> {
+    pub rule_set: &'a RuleSet,
+    /// Position of the rule in its rule set. The score threshold of a
+    /// scoring rule set comes after the last rule.
+    pub index: usize,
+    rule: &'a Rule,
+    /// Pattern of a rewrite action, compiled with the rule set
+    rewrite: Option<&'a Regex>,
+}
+
+impl<'a> RuleMatch<'a> {
+    pub fn new(rule_set: &'a RuleSet, index: usize) -> Self {
+        Self {
+            rule_set,
+            index,
+            rule: &rule_set.rules[index],
+            rewrite: None,
+        }
+    }
+
+    /// A match of the score threshold, see [`Scoring::rule`]
+    pub(crate) fn score_threshold(rule_set: &'a RuleSet, rule: &'a Rule) -> Self {
+        Self {
+            rule_set,
+            index: rule_set.rules.len(),
+            rule,
+            rewrite: None,
+        }
+    }
+
+    pub(crate) fn with_rewrite(self, rewrite: Option<&'a Regex>) -> Self {
+        Self { rewrite, ..self }
+    }
+
+    pub fn rule(&self) -> &'a Rule {
+        self.rule
+    }
+
+    /// Whether this is the score threshold of a scoring rule set rather
+    /// than one of its rules
+    pub fn is_score_threshold(&self) -> bool {
+        self.index == self.rule_set.rules.len()
+    }
+
+    /// The compiled pattern of the rule's rewrite action. Only matches
+    /// returned by a [`CompiledRuleSet`] carry one.
+    pub fn rewrite_pattern(&self) -> Option<&'a Regex> {
+        self.rewrite
+    }
+}
+
+impl fmt::Display for RuleMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = self.rule();
+        write!(f, "{} rule {}", self.rule_set.scope(), rule.key(self.index))?;
+        if let Some(name) = &rule.name {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that the regex or URL of an action is valid
+fn validate_action(action: &Action, label: &str) -> Result<()> {
+    match action {
+        Action::RewriteTitle { pattern, .. } | Action::RewriteContent { pattern, .. } => {
+            regex::Regex::new(pattern)
+                .with_context(|| format!("Invalid rewrite pattern in {}: '{}'", label, pattern))?;
+        }
+        Action::Webhook { url, retries, .. } => {
+            let parsed = reqwest::Url::parse(url)
+                .with_context(|| format!("Invalid webhook URL in {}: '{}'", label, url))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                anyhow::bail!("Webhook URL in {} must use http or https: '{}'", label, url);
             }
-        };
+            if retries.is_some_and(|retries| retries > MAX_RETRIES) {
+                anyhow::bail!(
+                    "Webhook in {} may retry at most {} times",
+                    label,
+                    MAX_RETRIES
+                );
+            }
+        }
+        Action::MarkRead
+        | Action::Remove
+        | Action::Bookmark
+        | Action::FetchContent
+        | Action::Save
+        | Action::Keep => {}
+    }
+    Ok(())
+}
 
-        match condition.operator {
-            Operator::Contains => field_value
-                .to_lowercase()
-                .contains(&condition.value.to_lowercase()),
-            Operator::NotContains => !field_value
-                .to_lowercase()
-                .contains(&condition.value.to_lowercase()),
-            Operator::Equals => field_value.eq_ignore_ascii_case(&condition.value),
-            Operator::NotEquals => !field_value.eq_ignore_ascii_case(&condition.value),
-            Operator::StartsWith => field_value
-                .to_lowercase()
-                .starts_with(&condition.value.to_lowercase()),
-            Operator::EndsWith => field_value
-                .to_lowercase()
-                .ends_with(&condition.value.to_lowercase()),
-            Operator::Matches => match regex::Regex::new(&condition.value) {
-                Ok(re) => re.is_match(field_value),
-                Err(_) => {
-                    warn!("Invalid regex pattern '{}' in condition", condition.value);
-                    false
-                }
-            },
+/// Check that a rule ID only uses characters that read well in logs and URLs,
+/// and cannot be mistaken for the position of a rule without an ID
+fn validate_rule_id(id: &str) -> Result<()> {
+    if is_numeric(id) {
+        anyhow::bail!(
+            "Invalid rule ID '{}', IDs made only of digits are kept for rule positions",
+            id
+        );
+    }
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid rule ID '{}', use letters, digits, '-' and '_' only",
+            id
+        );
+    }
+    Ok(())
+}
+
+/// Whether a string is empty or only made of digits, like a rule position
+fn is_numeric(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Lowercase ASCII letters and digits of a name, other characters replaced
+/// by single dashes, e.g. "Hide sponsored posts!" becomes "hide-sponsored-posts"
+fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Validate a condition tree node, using `label` to locate errors
+fn validate_node(node: &ConditionNode, tz: Tz, label: &str) -> Result<()> {
+    let children: &[ConditionNode] = match node {
+        ConditionNode::All { all } => all,
+        ConditionNode::Any { any } => any,
+        ConditionNode::Not { not } => std::slice::from_ref(not.as_ref()),
+        ConditionNode::AtLeast { at_least, of } => {
+            if *at_least == 0 || *at_least > of.len() {
+                anyhow::bail!(
+                    "{} needs at_least between 1 and its {} conditions, not {}",
+                    label,
+                    of.len(),
+                    at_least
+                );
+            }
+            of
         }
+        ConditionNode::Condition(condition) => {
+            if condition.value.trim().is_empty() {
+                anyhow::bail!("{} has an empty value", label);
+            }
+
+            // Compiling checks that the operator suits the field and that
+            // regexes, durations and timestamps parse
+            check_condition(condition, tz).map_err(|e| anyhow::anyhow!("{}: {:#}", label, e))?;
+
+            return Ok(());
+        }
+    };
+
+    if children.is_empty() {
+        anyhow::bail!("{} is an empty condition group", label);
     }
+
+    for (k, child) in children.iter().enumerate() {
+        validate_node(child, tz, &format!("{}.{}", label, k + 1))?;
+    }
+
+    Ok(())
 }
 
 /// Load all rule sets from a directory
@@ -282,40 +979,50 @@ pub fn load_rule_sets_from_dir<P: AsRef<Path>>(dir_path: P) -> Result<Vec<RuleSe
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::api::{Entry, Feed};
+    use crate::api::{Category, Enclosure, Entry, Feed, test_entry};
+    use crate::compiled::{CompiledRuleSet, evaluate_rule_sets};
+
+    /// A condition with the default options
+    fn condition(field: Field, operator: Operator, value: &str) -> Condition {
+        Condition {
+            field,
+            operator,
+            value: value.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A rule marking entries that match one condition as read
+    fn rule(condition: Condition) -> Rule {
+        Rule {
+            action: Some(Action::MarkRead),
+            conditions: vec![condition.into()],
+            ..Default::default()
+        }
+    }
 
     #[test]
     fn test_rule_evaluation() {
         let rule_set = RuleSet {
-            feed_id: 123,
+            feed_id: Some(123),
             enabled: true,
             rules: vec![Rule {
-                action: Action::MarkRead,
-                conditions: vec![Condition {
-                    field: Field::Title,
-                    operator: Operator::Contains,
-                    value: "advertisement".to_string(),
-                }],
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: "advertisement".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
-        let entry = Entry {
-            id: 1,
-            title: "This is an Advertisement".to_string(),
-            url: "https://example.com".to_string(),
-            content: "Some content".to_string(),
-            author: "Author".to_string(),
-            status: "unread".to_string(),
-            feed: Feed {
-                id: 123,
-                title: "Test Feed".to_string(),
-                site_url: "https://example.com".to_string(),
-                feed_url: "https://example.com/feed".to_string(),
-            },
-            published_at: "2024-01-01T00:00:00Z".to_string(),
-            created_at: "2024-01-01T00:00:00Z".to_string(),
-            tags: vec![],
-        };
+        let entry = test_entry("This is an Advertisement");
 
         let matches = rule_set.evaluate(&entry);
         assert_eq!(matches, vec![0]); // First rule (index 0)
@@ -324,74 +1031,1168 @@ mod tests {
     #[test]
     fn test_disabled_rule_set() {
         let rule_set = RuleSet {
-            feed_id: 123,
+            feed_id: Some(123),
             enabled: false,
             rules: vec![Rule {
-                action: Action::MarkRead,
-                conditions: vec![Condition {
-                    field: Field::Title,
-                    operator: Operator::Contains,
-                    value: "test".to_string(),
-                }],
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: "test".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
-        let entry = Entry {
-            id: 1,
-            title: "This is a test".to_string(),
-            url: "https://example.com".to_string(),
-            content: "Some content".to_string(),
-            author: "Author".to_string(),
-            status: "unread".to_string(),
-            feed: Feed {
-                id: 123,
-                title: "Test Feed".to_string(),
-                site_url: "https://example.com".to_string(),
-                feed_url: "https://example.com/feed".to_string(),
-            },
-            published_at: "2024-01-01T00:00:00Z".to_string(),
-            created_at: "2024-01-01T00:00:00Z".to_string(),
-            tags: vec![],
-        };
+        let entry = test_entry("This is a test");
 
         let matches = rule_set.evaluate(&entry);
         assert!(matches.is_empty());
     }
 
+    #[test]
+    fn test_rule_schedules() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+            timezone = "Europe/Berlin"
+            active_from = "2020-01-01"
+
+            [[rules]]
+            action = "markread"
+            active_until = "2020-07-20"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "spoiler"
+
+            [[rules]]
+            action = "markread"
+            active_from = "2999-01-01T18:00"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "spoiler"
+
+            [[rules]]
+            action = "markread"
+            active_days = "mon,tue,wed,thu,fri,sat,sun"
+            active_hours = "0-24"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "spoiler"
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+        assert_eq!(rule_set.schedule.active_from.as_deref(), Some("2020-01-01"));
+        assert_eq!(
+            rule_set.rules[2].schedule.active_hours.as_deref(),
+            Some("0-24")
+        );
+
+        let entry = test_entry("Final spoiler");
+        assert_eq!(rule_set.evaluate(&entry), vec![2]);
+        assert_eq!(rule_set.expired_rules(Utc::now()), vec![0]);
+        assert!(!rule_set.is_expired(Utc::now()));
+
+        // Schedules survive saving
+        let saved = toml::to_string_pretty(&rule_set).unwrap();
+        assert!(saved.contains("active_until = \"2020-07-20\""));
+        let reloaded: RuleSet = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.expired_rules(Utc::now()), vec![0]);
+
+        let mut expired = rule_set.clone();
+        expired.schedule.active_until = Some("2021-01-01".to_string());
+        assert!(expired.is_expired(Utc::now()));
+        assert!(expired.evaluate(&entry).is_empty());
+
+        let mut inverted = rule_set.clone();
+        inverted.rules[0].schedule.active_from = Some("2020-08-01".to_string());
+        assert!(inverted.validate().is_err());
+        inverted.rules[0].schedule = Schedule {
+            active_days: Some("someday".to_string()),
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
     #[test]
     fn test_tag_evaluation() {
         let rule_set = RuleSet {
-            feed_id: 123,
+            feed_id: Some(123),
             enabled: true,
             rules: vec![Rule {
-                action: Action::MarkRead,
-                conditions: vec![Condition {
-                    field: Field::Tag,
-                    operator: Operator::Matches,
-                    value: "(?i)sports".to_string(),
-                }],
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Tag,
+                        operator: Operator::Matches,
+                        value: "(?i)sports".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let entry = Entry {
-            id: 1,
-            title: "Test Article".to_string(),
-            url: "https://example.com".to_string(),
-            content: "Some content".to_string(),
-            author: "Author".to_string(),
-            status: "unread".to_string(),
-            feed: Feed {
-                id: 123,
-                title: "Test Feed".to_string(),
-                site_url: "https://example.com".to_string(),
-                feed_url: "https://example.com/feed".to_string(),
-            },
-            published_at: "2024-01-01T00:00:00Z".to_string(),
-            created_at: "2024-01-01T00:00:00Z".to_string(),
             tags: vec!["News".to_string(), "Sports".to_string()],
+            ..test_entry("Test Article")
         };
 
         let matches = rule_set.evaluate(&entry);
         assert_eq!(matches, vec![0]); // First rule (index 0)
     }
+
+    #[test]
+    fn test_condition_tree_evaluation() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+
+            [[rules]]
+            action = "markread"
+
+            [[rules.conditions]]
+            any = [
+                { field = "title", operator = "contains", value = "rust" },
+                { field = "author", operator = "equals", value = "Ferris" },
+            ]
+
+            [[rules.conditions]]
+            not = { field = "tag", operator = "equals", value = "keep" }
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+
+        let mut entry = Entry {
+            author: "Ferris".to_string(),
+            ..test_entry("Weekly news")
+        };
+        assert_eq!(rule_set.evaluate(&entry), vec![0]);
+
+        entry.tags = vec!["Keep".to_string()];
+        assert!(rule_set.evaluate(&entry).is_empty());
+
+        entry.tags.clear();
+        entry.author = "Someone else".to_string();
+        assert!(rule_set.evaluate(&entry).is_empty());
+    }
+
+    #[test]
+    fn test_at_least_groups() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+
+            [[rules]]
+            action = "markread"
+
+            [[rules.conditions]]
+            at_least = 2
+            of = [
+                { field = "title", operator = "containsword", value = "crypto" },
+                { field = "title", operator = "containsword", value = "token" },
+                { field = "content", operator = "containsword", value = "airdrop" },
+            ]
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+
+        let entry = |title: &str, content: &str| Entry {
+            content: content.to_string(),
+            ..test_entry(title)
+        };
+        assert!(
+            rule_set
+                .evaluate(&entry("Crypto winter", "Prices fell"))
+                .is_empty()
+        );
+        assert_eq!(
+            rule_set.evaluate(&entry("Crypto token launch", "")),
+            vec![0]
+        );
+        assert_eq!(
+            rule_set.evaluate(&entry("New token", "Claim the airdrop")),
+            vec![0]
+        );
+
+        // The group survives saving
+        let saved = toml::to_string_pretty(&rule_set).unwrap();
+        let reloaded: RuleSet = toml::from_str(&saved).unwrap();
+        assert!(matches!(
+            &reloaded.rules[0].conditions[0],
+            ConditionNode::AtLeast { at_least: 2, of } if of.len() == 3
+        ));
+
+        // The threshold must be reachable
+        for at_least in [0, 4] {
+            let mut invalid = rule_set.clone();
+            if let ConditionNode::AtLeast { at_least: k, .. } = &mut invalid.rules[0].conditions[0]
+            {
+                *k = at_least;
+            }
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_scoring_rules() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+
+            [scoring]
+            threshold = 5
+            action = "remove"
+
+            [[rules]]
+            id = "crypto"
+            score = 3
+            conditions = [{ field = "title", operator = "containsword", value = "crypto" }]
+
+            [[rules]]
+            id = "giveaway"
+            score = 3
+            conditions = [{ field = "content", operator = "contains", value = "giveaway" }]
+
+            [[rules]]
+            id = "trusted"
+            score = -4
+            conditions = [{ field = "author", operator = "equals", value = "Editor" }]
+
+            [[rules]]
+            action = "bookmark"
+            conditions = [{ field = "title", operator = "contains", value = "launch" }]
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+
+        let entry = |title: &str, content: &str, author: &str| Entry {
+            content: content.to_string(),
+            author: author.to_string(),
+            ..test_entry(title)
+        };
+
+        // One scored rule is not enough, and scored rules do not act
+        let card = rule_set
+            .score(&entry("Crypto news", "", "Someone"))
+            .unwrap();
+        assert_eq!((card.total, card.reached()), (3, false));
+        assert!(
+            rule_set
+                .evaluate(&entry("Crypto news", "", "Someone"))
+                .is_empty()
+        );
+
+        // Both reach the threshold, which acts after the other rules
+        let spam = entry("Crypto launch", "Join the giveaway", "Someone");
+        let card = rule_set.score(&spam).unwrap();
+        assert_eq!(card.to_string(), "6 of 5 (crypto +3, giveaway +3)");
+        assert_eq!(rule_set.evaluate(&spam), vec![3, 4]);
+        let compiled = CompiledRuleSet::compile(rule_set.clone()).unwrap();
+        let matches = evaluate_rule_sets(&[&compiled], &spam);
+        assert!(matches!(matches[1].rule().action, Some(Action::Remove)));
+        assert!(!matches[0].is_score_threshold());
+        assert!(matches[1].is_score_threshold());
+        assert_eq!(
+            matches[1].to_string(),
+            "feed 123 rule score (score of 5 or more)"
+        );
+
+        // A negative score pulls the total back under the threshold
+        let trusted = entry("Crypto launch", "Join the giveaway", "Editor");
+        assert_eq!(rule_set.score(&trusted).unwrap().total, 2);
+        assert_eq!(rule_set.evaluate(&trusted), vec![3]);
+
+        // Scores need a threshold, and the threshold needs scored rules
+        let mut unscored = rule_set.clone();
+        unscored.scoring = None;
+        assert!(unscored.validate().is_err());
+        assert!(unscored.score(&spam).is_none());
+        let mut no_scores = rule_set.clone();
+        no_scores.rules = vec![rule_set.rules[3].clone()];
+        assert!(no_scores.validate().is_err());
+
+        // Rules need an action unless they are scored, and may not have both
+        let mut actionless = rule_set.clone();
+        actionless.rules[3].action = None;
+        assert!(actionless.validate().is_err());
+        let mut both = rule_set.clone();
+        both.rules[0].action = Some(Action::MarkRead);
+        assert!(both.validate().is_err());
+
+        // Large scores add up without overflowing
+        let mut large = rule_set.clone();
+        large.rules[0].score = Some(i32::MAX);
+        large.rules[1].score = Some(i32::MAX);
+        assert_eq!(large.score(&spam).unwrap().total, 2 * i64::from(i32::MAX));
+        let mut reserved = rule_set.clone();
+        reserved.rules[3].id = Some(SCORE_RULE_ID.to_string());
+        assert!(reserved.validate().is_err());
+    }
+
+    #[test]
+    fn test_condition_tree_round_trip() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![Rule {
+                id: Some("bots".to_string()),
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Url,
+                        operator: Operator::StartsWith,
+                        value: "https://example.com".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                    ConditionNode::Not {
+                        not: Box::new(ConditionNode::All {
+                            all: vec![
+                                Condition {
+                                    field: Field::Title,
+                                    operator: Operator::Matches,
+                                    value: "^\\[Ad\\]".to_string(),
+                                    ..Default::default()
+                                }
+                                .into(),
+                                ConditionNode::Any {
+                                    any: vec![
+                                        Condition {
+                                            field: Field::Author,
+                                            operator: Operator::Equals,
+                                            value: "Bot".to_string(),
+                                            ..Default::default()
+                                        }
+                                        .into(),
+                                    ],
+                                },
+                            ],
+                        }),
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("feed_123.toml");
+        rule_set.save_to_file(&path).unwrap();
+        let loaded = RuleSet::load_from_file(&path).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&rule_set).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_condition_typos_are_reported() {
+        let parse = |conditions: &str| {
+            let toml_str = format!(
+                "feed_id = 123\nenabled = true\n[[rules]]\naction = \"markread\"\nconditions = [{}]",
+                conditions
+            );
+            toml::from_str::<RuleSet>(&toml_str)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let err = parse(r#"{ field = "title", operator = "containz", value = "ad" }"#);
+        assert!(err.contains("unknown variant `containz`"), "{}", err);
+        let err = parse(r#"{ any = [{ field = "titel", operator = "contains", value = "ad" }] }"#);
+        assert!(err.contains("unknown variant `titel`"), "{}", err);
+        let err = parse(r#"{ at_least = 1 }"#);
+        assert!(err.contains("missing field `of`"), "{}", err);
+    }
+
+    #[test]
+    fn test_empty_condition_group_rejected() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![Rule {
+                action: Some(Action::MarkRead),
+                conditions: vec![ConditionNode::Any { any: vec![] }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let err = rule_set.validate().unwrap_err();
+        assert!(err.to_string().contains("Rule 1 condition 1"));
+    }
+
+    #[test]
+    fn test_global_rule_set_scope() {
+        let toml_str = r#"
+            global = true
+            exclude_feeds = [7]
+            enabled = true
+
+            [[rules]]
+            action = "markread"
+
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "sponsored"
+        "#;
+        let global: RuleSet = toml::from_str(toml_str).unwrap();
+        global.validate().unwrap();
+
+        let feed = |id: u64| Feed {
+            id,
+            title: "Test Feed".to_string(),
+            site_url: "https://example.com".to_string(),
+            feed_url: "https://example.com/feed".to_string(),
+            category: None,
+        };
+
+        assert!(global.is_global());
+        assert!(global.applies_to_feed(&feed(123)));
+        assert!(!global.applies_to_feed(&feed(7)));
+
+        let feed_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(feed_set.applies_to_feed(&feed(123)));
+        assert!(!feed_set.applies_to_feed(&feed(7)));
+
+        let ambiguous = RuleSet {
+            feed_id: Some(123),
+            global: true,
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(ambiguous.validate().is_err());
+
+        let unscoped = RuleSet {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(unscoped.validate().is_err());
+    }
+
+    #[test]
+    fn test_evaluate_rule_sets_merges_matches() {
+        let feed_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(condition(Field::Title, Operator::Contains, "release")),
+                rule(condition(Field::Title, Operator::Contains, "weekly")),
+            ],
+            ..Default::default()
+        };
+        let global_set = RuleSet {
+            global: true,
+            enabled: true,
+            rules: vec![rule(condition(
+                Field::Title,
+                Operator::Contains,
+                "sponsored",
+            ))],
+            ..Default::default()
+        };
+
+        let entry = test_entry("Sponsored: weekly release");
+
+        let feed_set = CompiledRuleSet::compile(feed_set).unwrap();
+        let global_set = CompiledRuleSet::compile(global_set).unwrap();
+        let labels: Vec<String> = evaluate_rule_sets(&[&feed_set, &global_set], &entry)
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["feed 123 rule 1", "feed 123 rule 2", "global rule 1"]
+        );
+    }
+
+    #[test]
+    fn test_rule_priority_and_keep() {
+        let feed_set: RuleSet = toml::from_str(
+            r#"
+            feed_id = 123
+            enabled = true
+
+            [[rules]]
+            action = "bookmark"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "release"
+
+            [[rules]]
+            action = "keep"
+            priority = 10
+            [[rules.conditions]]
+            field = "author"
+            operator = "equals"
+            value = "Alice"
+            "#,
+        )
+        .unwrap();
+        feed_set.validate().unwrap();
+        let global_set: RuleSet = toml::from_str(
+            r#"
+            global = true
+            enabled = true
+
+            [[rules]]
+            action = "remove"
+            priority = 5
+            stop = true
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "sponsored"
+
+            [[rules]]
+            action = "markread"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "release"
+            "#,
+        )
+        .unwrap();
+        let feed_set = CompiledRuleSet::compile(feed_set).unwrap();
+        let global_set = CompiledRuleSet::compile(global_set).unwrap();
+
+        let entry = |title: &str, author: &str| Entry {
+            author: author.to_string(),
+            ..test_entry(title)
+        };
+        let labels = |title: &str, author: &str| -> Vec<String> {
+            evaluate_rule_sets(&[&feed_set, &global_set], &entry(title, author))
+                .iter()
+                .map(|m| m.to_string())
+                .collect()
+        };
+
+        // Equal priorities keep the order of rule sets and files
+        assert_eq!(
+            labels("New release", "Bob"),
+            vec!["feed 123 rule 1", "global rule 2"]
+        );
+        // Higher priorities go first, and stop ends the evaluation
+        assert_eq!(labels("Sponsored release", "Bob"), vec!["global rule 1"]);
+        // Keep exempts the entry from every later rule
+        assert_eq!(
+            labels("Sponsored release", "Alice"),
+            vec!["feed 123 rule 2"]
+        );
+
+        // The keep rule matched, but has nothing to do
+        let mut plan = crate::actions::ActionPlan::new();
+        let entry = entry("Sponsored release", "Alice");
+        plan.add(
+            &entry,
+            &evaluate_rule_sets(&[&feed_set, &global_set], &entry),
+        );
+        assert!(plan.is_empty());
+        assert_eq!(plan.summary(), "kept 1");
+    }
+
+    #[test]
+    fn test_rule_ids() {
+        let mut rule_set: RuleSet = toml::from_str(
+            r#"
+            global = true
+            enabled = true
+
+            [[rules]]
+            name = "Hide sponsored posts!"
+            description = "Ads disguised as articles"
+            action = "markread"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "sponsored"
+
+            [[rules]]
+            id = "rule-3"
+            action = "markread"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "webinar"
+
+            [[rules]]
+            action = "markread"
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "giveaway"
+            "#,
+        )
+        .unwrap();
+        rule_set.validate().unwrap();
+
+        let label = |rule_set: &RuleSet, index| RuleMatch::new(rule_set, index).to_string();
+        assert_eq!(label(&rule_set, 0), "global rule 1 (Hide sponsored posts!)");
+        assert_eq!(label(&rule_set, 1), "global rule rule-3");
+
+        rule_set.assign_rule_ids();
+        let ids: Vec<_> = rule_set.rules.iter().map(|r| r.id.as_deref()).collect();
+        assert_eq!(
+            ids,
+            [
+                Some("hide-sponsored-posts"),
+                Some("rule-3"),
+                Some("rule-3-2")
+            ]
+        );
+        assert_eq!(
+            label(&rule_set, 0),
+            "global rule hide-sponsored-posts (Hide sponsored posts!)"
+        );
+
+        // IDs must be unique and usable in logs
+        rule_set.rules[2].id = Some("rule-3".to_string());
+        assert!(rule_set.validate().is_err());
+        rule_set.rules[2].id = Some("rule 3".to_string());
+        assert!(rule_set.validate().is_err());
+
+        // Digits alone would read like the position of another rule
+        rule_set.rules[2].id = Some("2".to_string());
+        assert!(rule_set.validate().is_err());
+        rule_set.rules[2].id = None;
+        rule_set.rules[2].name = Some("2024".to_string());
+        rule_set.assign_rule_ids();
+        assert_eq!(rule_set.rules[2].id.as_deref(), Some("rule-3-2"));
+    }
+
+    #[test]
+    fn test_rule_ids_assigned_on_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("global.toml");
+        RuleSet {
+            global: true,
+            enabled: true,
+            rules: vec![
+                rule(condition(Field::Title, Operator::Contains, "sponsored")),
+                rule(condition(Field::Title, Operator::Contains, "webinar")),
+            ],
+            ..Default::default()
+        }
+        .save_to_file(&path)
+        .unwrap();
+
+        // Reordering a loaded rule set keeps the IDs from the file order
+        let mut rule_set = RuleSet::load_from_file(&path).unwrap();
+        rule_set.rules.reverse();
+        rule_set.assign_rule_ids();
+        let ids: Vec<_> = rule_set.rules.iter().map(|r| r.id.as_deref()).collect();
+        assert_eq!(ids, [Some("rule-2"), Some("rule-1")]);
+    }
+
+    #[test]
+    fn test_category_rule_set_scope() {
+        let toml_str = r#"
+            category_id = 4
+            enabled = true
+
+            [[rules]]
+            action = "markread"
+
+            [[rules.conditions]]
+            field = "title"
+            operator = "contains"
+            value = "sponsored"
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+        assert_eq!(rule_set.scope(), "category 4");
+        assert_eq!(rule_set.file_name(), "category_4.toml");
+
+        let mut feed = Feed {
+            id: 123,
+            title: "Test Feed".to_string(),
+            site_url: "https://example.com".to_string(),
+            feed_url: "https://example.com/feed".to_string(),
+            category: Some(Category {
+                id: 4,
+                title: "Tech".to_string(),
+            }),
+        };
+        assert!(rule_set.applies_to_feed(&feed));
+
+        feed.category = Some(Category {
+            id: 5,
+            title: "News".to_string(),
+        });
+        assert!(!rule_set.applies_to_feed(&feed));
+
+        feed.category = None;
+        assert!(!rule_set.applies_to_feed(&feed));
+
+        let ambiguous = RuleSet {
+            feed_id: Some(123),
+            category_id: Some(4),
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(ambiguous.validate().is_err());
+    }
+
+    #[test]
+    fn test_rewrite_action_parsing() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+
+            [[rules]]
+            action = { rewritetitle = { pattern = '^\[Sponsored\]\s*', replacement = "" } }
+
+            [[rules.conditions]]
+            field = "title"
+            operator = "startswith"
+            value = "[Sponsored]"
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+        assert!(matches!(
+            &rule_set.rules[0].action,
+            Some(Action::RewriteTitle { pattern, replacement })
+                if pattern == "^\\[Sponsored\\]\\s*" && replacement.is_empty()
+        ));
+
+        let serialized = toml::to_string_pretty(&rule_set).unwrap();
+        let reparsed: RuleSet = toml::from_str(&serialized).unwrap();
+        assert!(matches!(
+            reparsed.rules[0].action,
+            Some(Action::RewriteTitle { .. })
+        ));
+
+        let mut invalid = rule_set.clone();
+        invalid.rules[0].action = Some(Action::RewriteContent {
+            pattern: "(unclosed".to_string(),
+            replacement: String::new(),
+        });
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_retries_are_bounded() {
+        let webhook = |retries: u32| RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![Rule {
+                action: Some(Action::Webhook {
+                    url: "https://chat.example.com/hook".to_string(),
+                    secret: None,
+                    retries: Some(retries),
+                }),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: "release".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        webhook(MAX_RETRIES).validate().unwrap();
+        assert!(webhook(MAX_RETRIES + 1).validate().is_err());
+        assert!(webhook(u32::MAX).validate().is_err());
+    }
+
+    #[test]
+    fn test_fetch_content_parsing() {
+        let toml_str = r#"
+            feed_id = 123
+            enabled = true
+            fetch_content = true
+
+            [[rules]]
+            action = "fetchcontent"
+
+            [[rules.conditions]]
+            field = "content"
+            operator = "endswith"
+            value = "Read more"
+        "#;
+        let rule_set: RuleSet = toml::from_str(toml_str).unwrap();
+        rule_set.validate().unwrap();
+        assert!(rule_set.fetch_content);
+        assert!(matches!(
+            rule_set.rules[0].action,
+            Some(Action::FetchContent)
+        ));
+
+        // The flag is left out of files that do not use it
+        let mut plain = rule_set.clone();
+        plain.fetch_content = false;
+        assert!(
+            !toml::to_string_pretty(&plain)
+                .unwrap()
+                .contains("fetch_content")
+        );
+    }
+
+    #[test]
+    fn test_date_conditions() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            timezone: Some("America/New_York".to_string()),
+            rules: vec![
+                rule(condition(Field::Published, Operator::OlderThan, "2d")),
+                rule(condition(Field::Published, Operator::Before, "2024-07-20")),
+                Rule {
+                    action: Some(Action::MarkRead),
+                    // 2024-07-19T03:30:00Z is Thursday 23:30 in New York
+                    conditions: vec![
+                        condition(Field::Published, Operator::DayOfWeek, "thu").into(),
+                        condition(Field::Published, Operator::HourOfDay, "22-6").into(),
+                    ],
+                    ..Default::default()
+                },
+                rule(condition(Field::Created, Operator::NewerThan, "1h")),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let entry = Entry {
+            published_at: "2024-07-19T03:30:00Z".to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            ..test_entry("Match report")
+        };
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 1, 2, 3]);
+
+        let mut wrong_field = rule_set.clone();
+        wrong_field.rules[0] = rule(condition(Field::Title, Operator::Before, "2024-07-20"));
+        assert!(wrong_field.validate().is_err());
+
+        let mut bad_duration = rule_set.clone();
+        bad_duration.rules[0] = rule(condition(Field::Published, Operator::OlderThan, "two days"));
+        assert!(bad_duration.validate().is_err());
+
+        let mut bad_timezone = rule_set;
+        bad_timezone.timezone = Some("Nowhere/Special".to_string());
+        assert!(bad_timezone.validate().is_err());
+    }
+
+    #[test]
+    fn test_numeric_conditions() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(condition(Field::ReadingTime, Operator::GreaterThan, "20")),
+                rule(condition(Field::WordCount, Operator::LessThan, "50")),
+                rule(condition(Field::ContentLength, Operator::Between, "10-20")),
+                rule(condition(Field::ReadingTime, Operator::Equals, "5")),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let entry = Entry {
+            content: "<p>Four <b>words</b> of content</p>".to_string(),
+            reading_time: 25,
+            ..test_entry("Long read")
+        };
+        // "Four words of content" has 4 words and 21 characters
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 1]);
+
+        let mut not_a_number = rule_set.clone();
+        not_a_number.rules[0] = rule(condition(Field::ReadingTime, Operator::GreaterThan, "long"));
+        assert!(not_a_number.validate().is_err());
+
+        let mut text_operator = rule_set.clone();
+        text_operator.rules[0] = rule(condition(Field::WordCount, Operator::Contains, "5"));
+        assert!(text_operator.validate().is_err());
+
+        let mut numeric_operator = rule_set;
+        numeric_operator.rules[0] = rule(condition(Field::Title, Operator::GreaterThan, "5"));
+        assert!(numeric_operator.validate().is_err());
+    }
+
+    #[test]
+    fn test_case_and_accent_options() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                // Unicode case folding applies to every operator alike
+                rule(condition(Field::Author, Operator::Equals, "ÄRGER")),
+                rule(Condition {
+                    case_sensitive: Some(true),
+                    ..condition(Field::Title, Operator::Contains, "Café")
+                }),
+                rule(Condition {
+                    case_sensitive: Some(false),
+                    ignore_accents: true,
+                    ..condition(Field::Title, Operator::Matches, r"^cafe\b")
+                }),
+                rule(condition(Field::Title, Operator::Matches, "^café")),
+                rule(condition(Field::Tag, Operator::StartsWith, "strasse")),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let entry = Entry {
+            author: "ärger".to_string(),
+            tags: vec!["Straßenbahn".to_string()],
+            ..test_entry("CAFÉ opens on Straße")
+        };
+        // Rule 2 is case-sensitive and rule 4 a case-sensitive regex by default
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 2, 4]);
+
+        let mut numeric = rule_set;
+        numeric.rules[0] = rule(Condition {
+            case_sensitive: Some(true),
+            ..condition(Field::ReadingTime, Operator::GreaterThan, "5")
+        });
+        assert!(numeric.validate().is_err());
+    }
+
+    #[test]
+    fn test_word_operators() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(condition(Field::Title, Operator::ContainsWord, "AI")),
+                rule(condition(Field::Content, Operator::ContainsWord, "AI")),
+                rule(condition(
+                    Field::Content,
+                    Operator::ContainsWord,
+                    "new rules",
+                )),
+                rule(condition(Field::Content, Operator::Near, "EU regulation")),
+                rule(condition(
+                    Field::Tag,
+                    Operator::ContainsWord,
+                    "machine learning",
+                )),
+                rule(Condition {
+                    distance: Some(1),
+                    ..condition(Field::Tag, Operator::Near, "machine policy")
+                }),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let entry = Entry {
+            content: "The EU publishes new rules: AI-regulation starts in 2026".to_string(),
+            tags: vec!["Machine Learning".to_string(), "Policy".to_string()],
+            ..test_entry("Maintaining legacy code")
+        };
+        // "ai" is not a word of the title, and "machine" and "policy" are
+        // separate tags
+        assert_eq!(rule_set.evaluate(&entry), vec![1, 2, 3, 4]);
+
+        let mut invalid = rule_set.clone();
+        invalid.rules[0] = rule(condition(Field::Title, Operator::Near, "AI"));
+        assert!(invalid.validate().is_err());
+        invalid.rules[0] = rule(condition(Field::Title, Operator::ContainsWord, "..."));
+        assert!(invalid.validate().is_err());
+        invalid.rules[0] = rule_set.rules[5].clone();
+        if let ConditionNode::Condition(condition) = &mut invalid.rules[0].conditions[0] {
+            condition.operator = Operator::Contains;
+        }
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_html_content_fields() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(condition(Field::Content, Operator::Contains, "script")),
+                rule(condition(Field::RawContent, Operator::Contains, "<script")),
+                rule(condition(Field::Content, Operator::Contains, "Q&A")),
+                rule(condition(
+                    Field::LinkDomain,
+                    Operator::EndsWith,
+                    "medium.com",
+                )),
+                rule(condition(
+                    Field::LinkDomain,
+                    Operator::Equals,
+                    "example.com",
+                )),
+            ],
+            ..Default::default()
+        };
+
+        let entry = Entry {
+            content: r#"<p>A <a href="https://blog.medium.com/post">Q&amp;A</a></p>
+                <script src="tracker.js"></script>"#
+                .to_string(),
+            ..test_entry("Title")
+        };
+        assert_eq!(rule_set.evaluate(&entry), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_selector_conditions() {
+        let selector_rule =
+            |selector: &str, attribute: Option<&str>, operator: Operator, value: &str| {
+                rule(Condition {
+                    selector: Some(selector.to_string()),
+                    attribute: attribute.map(str::to_string),
+                    ..condition(Field::Content, operator, value)
+                })
+            };
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                selector_rule("h2:first-of-type", None, Operator::Contains, "sponsored"),
+                selector_rule("h2:first-of-type", None, Operator::Contains, "related"),
+                selector_rule(
+                    "a",
+                    Some("href"),
+                    Operator::Matches,
+                    r"^https://[^/]*\.?ads\.com/",
+                ),
+                selector_rule("img", Some("alt"), Operator::Equals, "logo"),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let entry = Entry {
+            content: r#"<h2>Sponsored post</h2><p><a href="https://track.ads.com/x">Buy</a></p>
+                <h2>Related</h2><img src="a.png">"#
+                .to_string(),
+            ..test_entry("Title")
+        };
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 2]);
+
+        let mut invalid = rule_set.clone();
+        invalid.rules[0] = selector_rule("h2[", None, Operator::Contains, "x");
+        assert!(invalid.validate().is_err());
+        if let ConditionNode::Condition(condition) = &mut invalid.rules[0].conditions[0] {
+            condition.selector = None;
+            condition.attribute = Some("href".to_string());
+        }
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_enclosure_conditions() {
+        let rule_set = RuleSet {
+            feed_id: Some(123),
+            enabled: true,
+            rules: vec![
+                rule(condition(
+                    Field::EnclosureType,
+                    Operator::StartsWith,
+                    "video/",
+                )),
+                rule(condition(
+                    Field::EnclosureType,
+                    Operator::Equals,
+                    "audio/mpeg",
+                )),
+                rule(condition(
+                    Field::EnclosureSize,
+                    Operator::GreaterThan,
+                    "100000000",
+                )),
+                rule(condition(
+                    Field::EnclosureSize,
+                    Operator::LessThan,
+                    "50000000",
+                )),
+                rule(condition(Field::EnclosureCount, Operator::Equals, "0")),
+                rule(condition(
+                    Field::EnclosureUrl,
+                    Operator::Contains,
+                    "/bonus/",
+                )),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let episode = Entry {
+            enclosures: vec![Enclosure {
+                url: "https://cdn.example.com/bonus/42.mp3".to_string(),
+                mime_type: "audio/mpeg".to_string(),
+                size: 41000000,
+            }],
+            ..test_entry("Episode 42")
+        };
+        assert_eq!(rule_set.evaluate(&episode), vec![1, 3, 5]);
+        assert_eq!(rule_set.evaluate(&test_entry("Episode 43")), vec![4]);
+    }
+
+    #[test]
+    fn test_feed_metadata_conditions() {
+        let rule_set = RuleSet {
+            global: true,
+            enabled: true,
+            rules: vec![
+                Rule {
+                    action: Some(Action::MarkRead),
+                    conditions: vec![
+                        condition(Field::SiteDomain, Operator::EndsWith, "medium.com").into(),
+                        condition(Field::Title, Operator::Contains, "top 10").into(),
+                    ],
+                    ..Default::default()
+                },
+                rule(condition(Field::CategoryTitle, Operator::Equals, "tech")),
+                rule(condition(
+                    Field::FeedTitle,
+                    Operator::ContainsWord,
+                    "weekly",
+                )),
+                rule(condition(Field::FeedUrl, Operator::EndsWith, ".atom")),
+            ],
+            ..Default::default()
+        };
+        rule_set.validate().unwrap();
+
+        let mut entry = Entry {
+            url: "https://writer.medium.com/top-10".to_string(),
+            content: String::new(),
+            feed: Feed {
+                id: 123,
+                title: "Writer Weekly".to_string(),
+                site_url: "https://writer.medium.com/".to_string(),
+                feed_url: "https://writer.medium.com/feed".to_string(),
+                category: Some(Category {
+                    id: 1,
+                    title: "Tech".to_string(),
+                }),
+            },
+            ..test_entry("Top 10 productivity hacks")
+        };
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 1, 2]);
+
+        // Without a site URL the domain comes from the feed URL
+        entry.feed.site_url = String::new();
+        entry.feed.category = None;
+        assert_eq!(rule_set.evaluate(&entry), vec![0, 2]);
+    }
 }
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How text is normalized before it is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextMode {
+    pub case_sensitive: bool,
+    pub ignore_accents: bool,
+}
+
+impl TextMode {
+    /// Number of distinct modes, for per-mode caches
+    pub const COUNT: usize = 4;
+
+    /// Position of this mode in per-mode caches
+    pub fn index(&self) -> usize {
+        usize::from(self.case_sensitive) * 2 + usize::from(self.ignore_accents)
+    }
+
+    /// The same mode, but keeping case. Regexes handle case themselves.
+    pub fn keep_case(self) -> Self {
+        Self {
+            case_sensitive: true,
+            ..self
+        }
+    }
+}
+
+/// Normalize text for comparison. The text is always brought into Unicode
+/// canonical composition (NFC) so composed and decomposed forms of "Ä" are
+/// equal. Case-insensitive modes apply full Unicode case folding, so "ß"
+/// matches "SS" and "Σ" matches "ς", and accent-insensitive modes drop
+/// combining marks after decomposing.
+pub fn normalize(text: &str, mode: TextMode) -> String {
+    let composed: String = if mode.ignore_accents {
+        text.nfd()
+            .filter(|&c| !is_combining_mark(c))
+            .nfc()
+            .collect()
+    } else {
+        text.nfc().collect()
+    };
+
+    if mode.case_sensitive {
+        composed
+    } else {
+        caseless::default_case_fold_str(&composed)
+    }
+}
+
+/// Split text into words along Unicode word boundaries, dropping
+/// punctuation and whitespace
+pub fn words(text: &str) -> Vec<String> {
+    text.unicode_words().map(str::to_string).collect()
+}
+
+/// Whether `phrase` appears as consecutive words in `words`
+pub fn contains_phrase(words: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && words.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Whether every term occurs in `words` with all of them at most `distance`
+/// words apart, e.g. "ai" and "regulation" within 3 words
+pub fn within(words: &[String], terms: &[String], distance: usize) -> bool {
+    // Positions of the words that are one of the terms, in order
+    let hits: Vec<(usize, usize)> = words
+        .iter()
+        .enumerate()
+        .filter_map(|(pos, word)| {
+            terms
+                .iter()
+                .position(|term| term == word)
+                .map(|term| (pos, term))
+        })
+        .collect();
+
+    // Slide a window over the hits, shrinking it from the left while it
+    // still holds every term, and check the smallest span found
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    let mut start = 0;
+    for &(end_pos, term) in &hits {
+        *counts.entry(term).or_default() += 1;
+
+        while counts.len() == terms.len() {
+            let (start_pos, start_term) = hits[start];
+            if end_pos - start_pos <= distance {
+                return true;
+            }
+            let count = counts.get_mut(&start_term).expect("term is counted");
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&start_term);
+            }
+            start += 1;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let folded = TextMode::default();
+        assert_eq!(normalize("Ärger", folded), normalize("ärger", folded));
+        // Decomposed "A" + combining diaeresis
+        assert_eq!(
+            normalize("A\u{308}rger", folded),
+            normalize("ärger", folded)
+        );
+        assert_eq!(normalize("STRASSE", folded), normalize("straße", folded));
+        assert_ne!(normalize("café", folded), normalize("cafe", folded));
+
+        let accents = TextMode {
+            ignore_accents: true,
+            ..TextMode::default()
+        };
+        assert_eq!(normalize("Café Crème", accents), "cafe creme");
+
+        let exact = TextMode {
+            case_sensitive: true,
+            ..TextMode::default()
+        };
+        assert_eq!(normalize("Café", exact), "Café");
+    }
+
+    #[test]
+    fn test_word_matching() {
+        let text = words(&normalize(
+            "Maintaining AI: new rules for AI-regulation, says the EU.",
+            TextMode::default(),
+        ));
+        let terms = |value: &str| words(value);
+
+        assert!(contains_phrase(&text, &terms("ai")));
+        assert!(!contains_phrase(&text, &terms("main")));
+        assert!(contains_phrase(&text, &terms("new rules")));
+        assert!(!contains_phrase(&text, &terms("rules new")));
+
+        // "ai" at 1 and 5, "regulation" at 6, "eu" at 9
+        assert!(within(&text, &terms("regulation ai"), 1));
+        assert!(within(&text, &terms("ai eu"), 4));
+        assert!(!within(&text, &terms("ai eu"), 3));
+        assert!(!within(&text, &terms("ai brexit"), 100));
+    }
+}
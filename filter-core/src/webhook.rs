@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::api::Entry;
+use crate::rules::RuleMatch;
+
+/// Header carrying the `sha256=<hex>` HMAC of the request body
+pub const SIGNATURE_HEADER: &str = "X-Miniflux-Filter-Signature";
+
+/// Retries after a failed delivery when the action does not configure them
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// Most retries an action may configure
+pub const MAX_RETRIES: u32 = 10;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait between two attempts
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Time after which a failing delivery is given up, whatever the retries
+const MAX_RETRY_TIME: Duration = Duration::from_secs(120);
+
+/// JSON body sent to webhook URLs
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub entry: WebhookEntry,
+    pub feed: WebhookFeed,
+    pub rule: WebhookRule,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEntry {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    pub author: String,
+    pub published_at: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookFeed {
+    pub id: u64,
+    pub title: String,
+    pub site_url: String,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookRule {
+    /// Scope of the rule set, e.g. "feed 123" or "global"
+    pub scope: String,
+    /// Position of the rule in its rule set, starting at 1
+    pub number: usize,
+    /// Stable ID of the rule, if it has one
+    pub id: Option<String>,
+    /// Name of the rule, if it has one
+    pub name: Option<String>,
+    /// Human readable label, e.g. "feed 123 rule 2" or "global rule spam (Spam)"
+    pub label: String,
+}
+
+impl WebhookPayload {
+    pub fn new(entry: &Entry, rule_match: &RuleMatch) -> Self {
+        Self {
+            entry: WebhookEntry {
+                id: entry.id,
+                title: entry.title.clone(),
+                url: entry.url.clone(),
+                author: entry.author.clone(),
+                published_at: entry.published_at.clone(),
+                tags: entry.tags.clone(),
+            },
+            feed: WebhookFeed {
+                id: entry.feed.id,
+                title: entry.feed.title.clone(),
+                site_url: entry.feed.site_url.clone(),
+                category: entry.feed.category.as_ref().map(|c| c.title.clone()),
+            },
+            rule: WebhookRule {
+                scope: rule_match.rule_set.scope(),
+                number: rule_match.index + 1,
+                id: rule_match.rule().id.clone(),
+                name: rule_match.rule().name.clone(),
+                label: rule_match.to_string(),
+            },
+        }
+    }
+}
+
+/// A webhook call planned for a matched entry
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub url: String,
+    pub secret: Option<String>,
+    pub retries: u32,
+    pub payload: WebhookPayload,
+}
+
+impl WebhookDelivery {
+    /// POST the payload, retrying with exponential backoff on failure. The
+    /// retries of one delivery are bounded in time, as they hold up the
+    /// poll cycle or web request that sends it.
+    pub async fn send(&self, client: &Client) -> Result<()> {
+        let body = serde_json::to_vec(&self.payload).context("Failed to serialize payload")?;
+        let started = Instant::now();
+
+        let mut attempt = 0;
+        loop {
+            let e = match self.send_once(client, &body).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            let delay = backoff(attempt);
+            if attempt >= self.retries || started.elapsed() + delay > MAX_RETRY_TIME {
+                return Err(e);
+            }
+            warn!(
+                "Webhook to {} failed ({}), retrying in {}s",
+                self.url,
+                e,
+                delay.as_secs()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_once(&self, client: &Client, body: &[u8]) -> Result<()> {
+        debug!(
+            "Sending webhook for entry {} to {}",
+            self.payload.entry.id, self.url
+        );
+
+        let mut request = client
+            .post(&self.url)
+            .timeout(TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body));
+        }
+
+        let response = request.send().await.context("Failed to send webhook")?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            anyhow::bail!("Webhook returned {}", response.status());
+        }
+    }
+}
+
+/// Wait before the retry following the given attempt: 1s, 2s, 4s and so on,
+/// up to [`MAX_DELAY`]
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.min(6)).min(MAX_DELAY)
+}
+
+/// Signature of a request body, in the `sha256=<hex>` format used by GitHub
+/// and most webhook receivers
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let delays: Vec<u64> = [0, 1, 2, 5, 6, 64, u32::MAX]
+            .into_iter()
+            .map(|attempt| backoff(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 32, 60, 60, 60]);
+    }
+}
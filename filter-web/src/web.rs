@@ -6,6 +6,7 @@ use axum::{
     response::{Html, Json, Response},
     routing::{delete, get, post, put},
 };
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -13,7 +14,10 @@ use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info};
 
-use filter_core::api::MinifluxClient;
+use filter_core::actions::{ActionHistory, ActionPlan, evaluate_with_full_content};
+use filter_core::api::{Entry, Feed, MinifluxClient};
+use filter_core::compiled::{CompiledRuleSet, RuleSetCache, ScoreCard, evaluate_rule_sets};
+use filter_core::filter::RuleStats;
 use filter_core::rules::{RuleSet, load_rule_sets_from_dir};
 
 #[derive(Clone)]
@@ -21,6 +25,9 @@ pub struct WebState {
     pub rules_dir: String,
     pub miniflux_client: MinifluxClient,
     pub log_collector: Option<crate::logging::WebLogCollector>,
+    pub history: Arc<ActionHistory>,
+    pub rule_stats: Arc<RuleStats>,
+    pub rule_set_cache: Arc<RuleSetCache>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -30,18 +37,57 @@ pub struct ApiResponse<T> {
     pub error: Option<String>,
 }
 
+/// A rule set as shown in the editor, with the rules whose active window is
+/// over flagged
+#[derive(Serialize)]
+pub struct RuleSetDetails {
+    #[serde(flatten)]
+    pub rule_set: RuleSet,
+    pub expired: bool,
+    pub expired_rules: Vec<usize>,
+}
+
+impl RuleSetDetails {
+    fn new(rule_set: RuleSet) -> Self {
+        let now = Utc::now();
+        Self {
+            expired: rule_set.is_expired(now),
+            expired_rules: rule_set.expired_rules(now),
+            rule_set,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct FeedInfo {
     pub id: u64,
     pub title: String,
     pub site_url: String,
     pub feed_url: String,
+    pub category_id: Option<u64>,
+    pub category_title: Option<String>,
     pub has_rules: bool,
+    pub has_category_rules: bool,
 }
 
-#[derive(Deserialize)]
-pub struct CreateRuleSetRequest {
-    pub feed_id: u64,
+impl FeedInfo {
+    fn new(
+        feed: Feed,
+        has_rules: bool,
+        categories_with_rules: &std::collections::HashSet<u64>,
+    ) -> Self {
+        let category_id = feed.category.as_ref().map(|c| c.id);
+        Self {
+            id: feed.id,
+            title: feed.title,
+            site_url: feed.site_url,
+            feed_url: feed.feed_url,
+            category_id,
+            category_title: feed.category.map(|c| c.title),
+            has_rules,
+            has_category_rules: category_id.is_some_and(|id| categories_with_rules.contains(&id)),
+        }
+    }
 }
 
 pub async fn start_web_server(
@@ -49,11 +95,16 @@ pub async fn start_web_server(
     miniflux_client: MinifluxClient,
     port: u16,
     log_collector: Option<crate::logging::WebLogCollector>,
+    history: Arc<ActionHistory>,
+    rule_stats: Arc<RuleStats>,
 ) -> Result<()> {
     let state = WebState {
         rules_dir,
         miniflux_client,
         log_collector,
+        history,
+        rule_stats,
+        rule_set_cache: Arc::new(RuleSetCache::default()),
     };
 
     let app = Router::new()
@@ -68,10 +119,27 @@ pub async fn start_web_server(
         .route("/api/rules/{feed_id}", get(get_rule_set))
         .route("/api/rules/{feed_id}", put(update_rule_set))
         .route("/api/rules/{feed_id}", delete(delete_rule_set))
+        .route(
+            "/api/rules/category/{category_id}",
+            get(get_category_rule_set),
+        )
+        .route(
+            "/api/rules/category/{category_id}",
+            put(update_category_rule_set),
+        )
+        .route(
+            "/api/rules/category/{category_id}",
+            delete(delete_category_rule_set),
+        )
         .route("/api/feeds", get(list_feeds))
         .route("/api/feeds/{feed_id}", get(get_feed))
         .route("/api/stats", get(get_stats))
+        .route("/api/preview", post(preview_rule_set))
         .route("/api/execute/{feed_id}", post(execute_filter))
+        .route(
+            "/api/execute/category/{category_id}",
+            post(execute_category_filter),
+        )
         .route("/api/logs", get(get_logs))
         .route("/api/logs/{feed_id}", get(get_logs_for_feed))
         .route("/api/logs", delete(clear_logs))
@@ -157,7 +225,28 @@ async fn list_rule_sets(State(state): State<Arc<WebState>>) -> Json<ApiResponse<
 async fn get_rule_set(
     Path(feed_id): Path<u64>,
     State(state): State<Arc<WebState>>,
-) -> Result<Json<ApiResponse<RuleSet>>, StatusCode> {
+) -> Result<Json<ApiResponse<RuleSetDetails>>, StatusCode> {
+    find_rule_set(&state, |rs| rs.feed_id == Some(feed_id), "feed", feed_id)
+}
+
+async fn get_category_rule_set(
+    Path(category_id): Path<u64>,
+    State(state): State<Arc<WebState>>,
+) -> Result<Json<ApiResponse<RuleSetDetails>>, StatusCode> {
+    find_rule_set(
+        &state,
+        |rs| rs.category_id == Some(category_id),
+        "category",
+        category_id,
+    )
+}
+
+fn find_rule_set(
+    state: &WebState,
+    matches: impl Fn(&RuleSet) -> bool,
+    scope: &str,
+    id: u64,
+) -> Result<Json<ApiResponse<RuleSetDetails>>, StatusCode> {
     let rule_sets = match load_rule_sets_from_dir(&state.rules_dir) {
         Ok(sets) => sets,
         Err(e) => {
@@ -166,49 +255,55 @@ async fn get_rule_set(
         }
     };
 
-    if let Some(rule_set) = rule_sets.into_iter().find(|rs| rs.feed_id == feed_id) {
+    if let Some(rule_set) = rule_sets.into_iter().find(|rs| matches(rs)) {
         Ok(Json(ApiResponse {
             success: true,
-            data: Some(rule_set),
+            data: Some(RuleSetDetails::new(rule_set)),
             error: None,
         }))
     } else {
         Ok(Json(ApiResponse {
             success: false,
             data: None,
-            error: Some(format!("Rule set for feed {} not found", feed_id)),
+            error: Some(format!("Rule set for {} {} not found", scope, id)),
         }))
     }
 }
 
 async fn create_rule_set(
     State(state): State<Arc<WebState>>,
-    Json(request): Json<CreateRuleSetRequest>,
-) -> Json<ApiResponse<String>> {
-    let rule_set = RuleSet {
-        feed_id: request.feed_id,
-        enabled: true,
-        rules: Vec::new(),
-    };
-
-    let filename = format!("{}/feed_{}.toml", state.rules_dir, request.feed_id);
+    Json(mut rule_set): Json<RuleSet>,
+) -> SaveResponse {
+    let filename = format!("{}/{}", state.rules_dir, rule_set.file_name());
+    if std::path::Path::new(&filename).exists() {
+        return Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!(
+                "A rule set for {} already exists",
+                rule_set.scope()
+            )),
+        }));
+    }
+    rule_set.assign_rule_ids();
+    check_lists(&state, &rule_set)?;
 
     match rule_set.save_to_file(&filename) {
         Ok(_) => {
-            info!("Created new rule set for feed {}", request.feed_id);
-            Json(ApiResponse {
+            info!("Created new {} rule set", rule_set.scope());
+            Ok(Json(ApiResponse {
                 success: true,
-                data: Some(format!("Rule set created for feed {}", request.feed_id)),
+                data: Some(format!("Rule set created for {}", rule_set.scope())),
                 error: None,
-            })
+            }))
         }
         Err(e) => {
             error!("Failed to create rule set: {}", e);
-            Json(ApiResponse {
+            Ok(Json(ApiResponse {
                 success: false,
                 data: None,
                 error: Some(e.to_string()),
-            })
+            }))
         }
     }
 }
@@ -217,42 +312,112 @@ async fn update_rule_set(
     Path(feed_id): Path<u64>,
     State(state): State<Arc<WebState>>,
     Json(rule_set): Json<RuleSet>,
-) -> Json<ApiResponse<String>> {
-    if rule_set.feed_id != feed_id {
-        return Json(ApiResponse {
+) -> SaveResponse {
+    if rule_set.feed_id != Some(feed_id) {
+        return Ok(Json(ApiResponse {
             success: false,
             data: None,
             error: Some("Feed ID mismatch".to_string()),
-        });
+        }));
+    }
+
+    save_rule_set(&state, rule_set)
+}
+
+async fn update_category_rule_set(
+    Path(category_id): Path<u64>,
+    State(state): State<Arc<WebState>>,
+    Json(rule_set): Json<RuleSet>,
+) -> SaveResponse {
+    if rule_set.category_id != Some(category_id) {
+        return Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some("Category ID mismatch".to_string()),
+        }));
     }
 
-    let filename = format!("{}/feed_{}.toml", state.rules_dir, feed_id);
+    save_rule_set(&state, rule_set)
+}
+
+/// Response of the handlers saving a rule set, a 400 for rule sets that
+/// cannot be compiled
+type SaveResponse = Result<Json<ApiResponse<String>>, (StatusCode, Json<ApiResponse<String>>)>;
+
+fn save_rule_set(state: &WebState, mut rule_set: RuleSet) -> SaveResponse {
+    let filename = format!("{}/{}", state.rules_dir, rule_set.file_name());
+    rule_set.assign_rule_ids();
+    check_lists(state, &rule_set)?;
 
     match rule_set.save_to_file(&filename) {
         Ok(_) => {
-            info!("Updated rule set for feed {}", feed_id);
-            Json(ApiResponse {
+            info!("Updated {} rule set", rule_set.scope());
+            Ok(Json(ApiResponse {
                 success: true,
-                data: Some(format!("Rule set updated for feed {}", feed_id)),
+                data: Some(format!("Rule set updated for {}", rule_set.scope())),
                 error: None,
-            })
+            }))
         }
         Err(e) => {
             error!("Failed to update rule set: {}", e);
-            Json(ApiResponse {
+            Ok(Json(ApiResponse {
                 success: false,
                 data: None,
                 error: Some(e.to_string()),
-            })
+            }))
         }
     }
 }
 
+/// Compile a rule set against the keyword lists of the rules directory, so
+/// that it is not saved when it names a missing list. The rule set would
+/// fail to load and stop filtering.
+fn check_lists(
+    state: &WebState,
+    rule_set: &RuleSet,
+) -> Result<(), (StatusCode, Json<ApiResponse<String>>)> {
+    match state
+        .rule_set_cache
+        .compile(&state.rules_dir, rule_set.clone())
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(format!("Invalid rule set: {:#}", e)),
+            }),
+        )),
+    }
+}
+
 async fn delete_rule_set(
     Path(feed_id): Path<u64>,
     State(state): State<Arc<WebState>>,
 ) -> Json<ApiResponse<String>> {
-    // Find the actual rule file for this feed ID by scanning the directory
+    remove_rule_set(&state, |rs| rs.feed_id == Some(feed_id), "feed", feed_id)
+}
+
+async fn delete_category_rule_set(
+    Path(category_id): Path<u64>,
+    State(state): State<Arc<WebState>>,
+) -> Json<ApiResponse<String>> {
+    remove_rule_set(
+        &state,
+        |rs| rs.category_id == Some(category_id),
+        "category",
+        category_id,
+    )
+}
+
+fn remove_rule_set(
+    state: &WebState,
+    matches: impl Fn(&RuleSet) -> bool,
+    scope: &str,
+    id: u64,
+) -> Json<ApiResponse<String>> {
+    // Find the actual rule file by scanning the directory
     let rules_dir = std::path::Path::new(&state.rules_dir);
 
     if !rules_dir.exists() {
@@ -263,7 +428,7 @@ async fn delete_rule_set(
         });
     }
 
-    // Look for any TOML file that contains this feed_id
+    // Look for any TOML file that contains this rule set
     let dir_entries = match std::fs::read_dir(rules_dir) {
         Ok(entries) => entries,
         Err(e) => {
@@ -279,29 +444,28 @@ async fn delete_rule_set(
     for entry in dir_entries.flatten() {
         let path = entry.path();
         if path.extension().and_then(|s| s.to_str()) == Some("toml") {
-            // Try to parse this TOML file to see if it matches our feed_id
-            if let Ok(content) = std::fs::read_to_string(&path) {
-                if let Ok(rule_set) = toml::from_str::<RuleSet>(&content) {
-                    if rule_set.feed_id == feed_id {
-                        // This is the file we want to delete
-                        match std::fs::remove_file(&path) {
-                            Ok(_) => {
-                                info!("Deleted rule set for feed {} from {:?}", feed_id, path);
-                                return Json(ApiResponse {
-                                    success: true,
-                                    data: Some(format!("Rule set deleted for feed {}", feed_id)),
-                                    error: None,
-                                });
-                            }
-                            Err(e) => {
-                                error!("Failed to delete rule file {:?}: {}", path, e);
-                                return Json(ApiResponse {
-                                    success: false,
-                                    data: None,
-                                    error: Some(format!("Failed to delete rule file: {}", e)),
-                                });
-                            }
-                        }
+            // Try to parse this TOML file to see if it is the one we want
+            if let Ok(content) = std::fs::read_to_string(&path)
+                && let Ok(rule_set) = toml::from_str::<RuleSet>(&content)
+                && matches(&rule_set)
+            {
+                // This is the file we want to delete
+                match std::fs::remove_file(&path) {
+                    Ok(_) => {
+                        info!("Deleted rule set for {} {} from {:?}", scope, id, path);
+                        return Json(ApiResponse {
+                            success: true,
+                            data: Some(format!("Rule set deleted for {} {}", scope, id)),
+                            error: None,
+                        });
+                    }
+                    Err(e) => {
+                        error!("Failed to delete rule file {:?}: {}", path, e);
+                        return Json(ApiResponse {
+                            success: false,
+                            data: None,
+                            error: Some(format!("Failed to delete rule file: {}", e)),
+                        });
                     }
                 }
             }
@@ -311,7 +475,7 @@ async fn delete_rule_set(
     Json(ApiResponse {
         success: false,
         data: None,
-        error: Some(format!("Rule set for feed {} not found", feed_id)),
+        error: Some(format!("Rule set for {} {} not found", scope, id)),
     })
 }
 
@@ -331,19 +495,18 @@ async fn list_feeds(State(state): State<Arc<WebState>>) -> Json<ApiResponse<Vec<
         }
     };
 
-    // Get existing rule sets to determine which feeds have rules
+    // Get existing rule sets to determine which feeds and categories have rules
     let rule_sets = load_rule_sets_from_dir(&state.rules_dir).unwrap_or_default();
     let feeds_with_rules: std::collections::HashSet<u64> =
-        rule_sets.into_iter().map(|rs| rs.feed_id).collect();
+        rule_sets.iter().filter_map(|rs| rs.feed_id).collect();
+    let categories_with_rules: std::collections::HashSet<u64> =
+        rule_sets.iter().filter_map(|rs| rs.category_id).collect();
 
     let feed_info: Vec<FeedInfo> = feeds
         .into_iter()
-        .map(|feed| FeedInfo {
-            id: feed.id,
-            title: feed.title,
-            site_url: feed.site_url,
-            feed_url: feed.feed_url,
-            has_rules: feeds_with_rules.contains(&feed.id),
+        .map(|feed| {
+            let has_rules = feeds_with_rules.contains(&feed.id);
+            FeedInfo::new(feed, has_rules, &categories_with_rules)
         })
         .collect();
 
@@ -378,17 +541,13 @@ async fn get_feed(
 
     match feed {
         Some(feed) => {
-            // Check if this feed has rules
+            // Check if this feed or its category has rules
             let rule_sets = load_rule_sets_from_dir(&state.rules_dir).unwrap_or_default();
-            let has_rules = rule_sets.iter().any(|rs| rs.feed_id == feed_id);
-
-            let feed_info = FeedInfo {
-                id: feed.id,
-                title: feed.title,
-                site_url: feed.site_url,
-                feed_url: feed.feed_url,
-                has_rules,
-            };
+            let has_rules = rule_sets.iter().any(|rs| rs.feed_id == Some(feed_id));
+            let categories_with_rules: std::collections::HashSet<u64> =
+                rule_sets.iter().filter_map(|rs| rs.category_id).collect();
+
+            let feed_info = FeedInfo::new(feed, has_rules, &categories_with_rules);
 
             Json(ApiResponse {
                 success: true,
@@ -409,13 +568,21 @@ async fn get_stats(State(state): State<Arc<WebState>>) -> Json<ApiResponse<serde
 
     let total_rule_sets = rule_sets.len();
     let enabled_rule_sets = rule_sets.iter().filter(|rs| rs.is_enabled()).count();
+    let global_rule_sets = rule_sets.iter().filter(|rs| rs.is_global()).count();
+    let category_rule_sets = rule_sets
+        .iter()
+        .filter(|rs| rs.category_id.is_some())
+        .count();
     let total_rules = rule_sets.iter().map(|rs| rs.rules.len()).sum::<usize>();
 
     let stats = serde_json::json!({
         "total_rule_sets": total_rule_sets,
         "enabled_rule_sets": enabled_rule_sets,
+        "global_rule_sets": global_rule_sets,
+        "category_rule_sets": category_rule_sets,
         "total_rules": total_rules,
-        "feeds_with_rules": rule_sets.iter().map(|rs| rs.feed_id).collect::<Vec<_>>()
+        "feeds_with_rules": rule_sets.iter().filter_map(|rs| rs.feed_id).collect::<Vec<_>>(),
+        "rule_matches": state.rule_stats.counts()
     });
 
     Json(ApiResponse {
@@ -437,7 +604,7 @@ async fn execute_filter(
     State(state): State<Arc<WebState>>,
 ) -> Json<ApiResponse<ExecuteResult>> {
     // Load the rule set for this feed
-    let rule_sets = match load_rule_sets_from_dir(&state.rules_dir) {
+    let rule_sets = match state.rule_set_cache.load(&state.rules_dir) {
         Ok(sets) => sets,
         Err(e) => {
             error!("Failed to load rule sets: {}", e);
@@ -450,7 +617,11 @@ async fn execute_filter(
     };
 
     // Find the rule set for this feed
-    let rule_set = match rule_sets.into_iter().find(|rs| rs.feed_id == feed_id) {
+    let rule_set = match rule_sets
+        .iter()
+        .map(|rs| rs.rule_set())
+        .find(|rs| rs.feed_id == Some(feed_id))
+    {
         Some(rs) => rs,
         None => {
             return Json(ApiResponse {
@@ -486,54 +657,144 @@ async fn execute_filter(
         }
     };
 
+    filter_entries(&state, &rule_sets, entries, &format!("feed {}", feed_id)).await
+}
+
+async fn execute_category_filter(
+    Path(category_id): Path<u64>,
+    State(state): State<Arc<WebState>>,
+) -> Json<ApiResponse<ExecuteResult>> {
+    // Load the rule set for this category
+    let rule_sets = match state.rule_set_cache.load(&state.rules_dir) {
+        Ok(sets) => sets,
+        Err(e) => {
+            error!("Failed to load rule sets: {}", e);
+            return Json(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(format!("Failed to load rule sets: {}", e)),
+            });
+        }
+    };
+
+    // Find the rule set for this category
+    let rule_set = match rule_sets
+        .iter()
+        .map(|rs| rs.rule_set())
+        .find(|rs| rs.category_id == Some(category_id))
+    {
+        Some(rs) => rs,
+        None => {
+            return Json(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(format!("No rule set found for category {}", category_id)),
+            });
+        }
+    };
+
+    if !rule_set.is_enabled() {
+        return Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Rule set for category {} is disabled", category_id)),
+        });
+    }
+
+    // Fetch unread entries for every feed in this category
+    let entries = match state
+        .miniflux_client
+        .get_unread_entries_for_category(category_id)
+        .await
+    {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!(
+                "Failed to fetch entries for category {}: {}",
+                category_id, e
+            );
+            return Json(ApiResponse {
+                success: false,
+                data: None,
+                error: Some(format!("Failed to fetch entries: {}", e)),
+            });
+        }
+    };
+
+    filter_entries(
+        &state,
+        &rule_sets,
+        entries,
+        &format!("category {}", category_id),
+    )
+    .await
+}
+
+/// Apply every enabled rule set to the entries of the feeds they belong to
+async fn filter_entries(
+    state: &WebState,
+    rule_sets: &[Arc<CompiledRuleSet>],
+    mut entries: Vec<Entry>,
+    scope: &str,
+) -> Json<ApiResponse<ExecuteResult>> {
     if entries.is_empty() {
         return Json(ApiResponse {
             success: true,
             data: Some(ExecuteResult {
                 processed: 0,
                 filtered: 0,
-                message: "No unread entries found for this feed".to_string(),
+                message: format!("No unread entries found for {}", scope),
             }),
             error: None,
         });
     }
 
-    let mut entries_to_mark = Vec::new();
+    let mut plan = ActionPlan::with_history(state.history.clone());
+
+    // Evaluate each entry against the feed, category and global rule sets
+    // that apply to it
+    for entry in &mut entries {
+        let applicable_sets: Vec<&CompiledRuleSet> = rule_sets
+            .iter()
+            .map(|rs| rs.as_ref())
+            .filter(|rs| {
+                let rule_set = rs.rule_set();
+                rule_set.is_enabled() && rule_set.applies_to_feed(&entry.feed)
+            })
+            .collect();
 
-    // Evaluate each entry against the rule set
-    for entry in &entries {
-        let matching_rules = rule_set.evaluate(entry);
-        if !matching_rules.is_empty() {
-            entries_to_mark.push(entry.id);
-        }
+        let matches = evaluate_with_full_content(
+            &state.miniflux_client,
+            &state.history,
+            &applicable_sets,
+            entry,
+        )
+        .await;
+        state.rule_stats.record(&matches);
+        plan.add(entry, &matches);
     }
 
-    // Mark matching entries as read
-    if !entries_to_mark.is_empty() {
-        if let Err(e) = state
-            .miniflux_client
-            .mark_entries_as_read(entries_to_mark.clone())
-            .await
-        {
-            error!("Failed to mark entries as read for feed {}: {}", feed_id, e);
-            return Json(ApiResponse {
-                success: false,
-                data: None,
-                error: Some(format!("Failed to mark entries as read: {}", e)),
-            });
-        }
+    // Apply the actions of the matching rules
+    if let Err(e) = plan.execute(&state.miniflux_client).await {
+        error!("Failed to apply actions for {}: {}", scope, e);
+        return Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Failed to apply actions: {}", e)),
+        });
     }
 
-    let message = if entries_to_mark.is_empty() {
+    let message = if plan.matched() == 0 {
         format!(
             "Processed {} entries, no entries matched the rules",
             entries.len()
         )
     } else {
         format!(
-            "Processed {} entries, marked {} as read",
+            "Processed {} entries, {} matched: {}",
             entries.len(),
-            entries_to_mark.len()
+            plan.matched(),
+            plan.summary()
         )
     };
 
@@ -541,13 +802,96 @@ async fn execute_filter(
         success: true,
         data: Some(ExecuteResult {
             processed: entries.len(),
-            filtered: entries_to_mark.len(),
+            filtered: plan.matched(),
             message,
         }),
         error: None,
     })
 }
 
+/// How a rule set being edited treats an unread entry
+#[derive(Serialize)]
+pub struct PreviewEntry {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    /// The rules whose actions would apply
+    pub matches: Vec<String>,
+    pub score: Option<ScoreCard>,
+}
+
+/// Evaluate a rule set from the editor against the unread entries of its
+/// feed or category, without saving it or applying any action
+async fn preview_rule_set(
+    State(state): State<Arc<WebState>>,
+    Json(mut rule_set): Json<RuleSet>,
+) -> Json<ApiResponse<Vec<PreviewEntry>>> {
+    let error = |message: String| {
+        Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(message),
+        })
+    };
+
+    // Name rules the way saving would
+    rule_set.assign_rule_ids();
+    if let Err(e) = rule_set.validate() {
+        return error(format!("Invalid rule set: {:#}", e));
+    }
+    let scope = rule_set.scope();
+    let (feed_id, category_id) = (rule_set.feed_id, rule_set.category_id);
+    let compiled = match state.rule_set_cache.compile(&state.rules_dir, rule_set) {
+        Ok(compiled) => compiled,
+        Err(e) => return error(format!("Failed to compile rule set: {:#}", e)),
+    };
+
+    let entries = match (feed_id, category_id) {
+        (Some(feed_id), _) => {
+            state
+                .miniflux_client
+                .get_unread_entries_for_feed(feed_id)
+                .await
+        }
+        (None, Some(category_id)) => {
+            state
+                .miniflux_client
+                .get_unread_entries_for_category(category_id)
+                .await
+        }
+        (None, None) => state.miniflux_client.get_unread_entries().await,
+    };
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to fetch entries for {}: {}", scope, e);
+            return error(format!("Failed to fetch entries: {}", e));
+        }
+    };
+
+    // A global rule set leaves out its excluded feeds
+    let preview = entries
+        .iter()
+        .filter(|entry| compiled.rule_set().applies_to_feed(&entry.feed))
+        .map(|entry| PreviewEntry {
+            id: entry.id,
+            title: entry.title.clone(),
+            url: entry.url.clone(),
+            matches: evaluate_rule_sets(&[&compiled], entry)
+                .iter()
+                .map(|rule_match| rule_match.to_string())
+                .collect(),
+            score: compiled.score(entry),
+        })
+        .collect();
+
+    Json(ApiResponse {
+        success: true,
+        data: Some(preview),
+        error: None,
+    })
+}
+
 async fn get_logs(
     State(state): State<Arc<WebState>>,
 ) -> Json<ApiResponse<Vec<crate::logging::LogEntry>>> {
@@ -638,10 +982,18 @@ mod tests {
             rules_dir,
             miniflux_client,
             log_collector: None,
+            history: Arc::new(ActionHistory::default()),
+            rule_stats: Arc::new(RuleStats::default()),
+            rule_set_cache: Arc::new(RuleSetCache::default()),
         };
 
         Router::new()
+            .route("/api/rules", post(create_rule_set))
             .route("/api/rules/{feed_id}", put(update_rule_set))
+            .route(
+                "/api/rules/category/{category_id}",
+                put(update_category_rule_set),
+            )
             .with_state(Arc::new(state))
     }
 
@@ -656,16 +1008,22 @@ mod tests {
 
         // Create a rule set with a valid rule
         let rule_set = RuleSet {
-            feed_id: 123,
+            feed_id: Some(123),
             enabled: true,
             rules: vec![Rule {
-                action: Action::MarkRead,
-                conditions: vec![Condition {
-                    field: Field::Title,
-                    operator: Operator::Contains,
-                    value: "test".to_string(),
-                }],
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: "test".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         // Create the request
@@ -692,4 +1050,140 @@ mod tests {
         assert!(api_response.success);
         assert!(api_response.error.is_none());
     }
+
+    #[tokio::test]
+    async fn test_create_rule_set() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules_dir = temp_dir.path().to_string_lossy().to_string();
+        let app = create_test_app(rules_dir);
+
+        let create = |app: Router| async move {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/rules")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"feed_id": 123}"#))
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<ApiResponse<String>>(&body).unwrap()
+        };
+
+        // A feed ID is enough to create an empty, enabled rule set
+        assert!(create(app.clone()).await.success);
+        let saved = RuleSet::load_from_file(temp_dir.path().join("feed_123.toml")).unwrap();
+        assert!(saved.enabled);
+        assert!(saved.rules.is_empty());
+
+        // An existing rule set is not overwritten
+        let api_response = create(app).await;
+        assert!(!api_response.success);
+        assert_eq!(
+            api_response.error.as_deref(),
+            Some("A rule set for feed 123 already exists")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_rule_set_with_unknown_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules_dir = temp_dir.path().to_string_lossy().to_string();
+        let app = create_test_app(rules_dir);
+
+        let save = |app: Router| async move {
+            let rule_set = RuleSet {
+                feed_id: Some(123),
+                enabled: true,
+                rules: vec![Rule {
+                    action: Some(Action::MarkRead),
+                    conditions: vec![
+                        Condition {
+                            field: Field::Title,
+                            operator: Operator::InList,
+                            value: "spam".to_string(),
+                            ..Default::default()
+                        }
+                        .into(),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            let request = Request::builder()
+                .method("PUT")
+                .uri("/api/rules/123")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&rule_set).unwrap()))
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        };
+
+        // The rule set would not load, so it is not saved
+        assert_eq!(save(app.clone()).await, StatusCode::BAD_REQUEST);
+        assert!(!temp_dir.path().join("feed_123.toml").exists());
+
+        let lists_dir = temp_dir.path().join("lists");
+        std::fs::create_dir(&lists_dir).unwrap();
+        std::fs::write(lists_dir.join("spam.txt"), "casino\n").unwrap();
+        assert_eq!(save(app).await, StatusCode::OK);
+        assert!(temp_dir.path().join("feed_123.toml").exists());
+    }
+
+    #[tokio::test]
+    async fn test_submit_category_rule_set() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules_dir = temp_dir.path().to_string_lossy().to_string();
+        let app = create_test_app(rules_dir);
+
+        let rule_set = RuleSet {
+            category_id: Some(4),
+            enabled: true,
+            rules: vec![Rule {
+                action: Some(Action::MarkRead),
+                conditions: vec![
+                    Condition {
+                        field: Field::Title,
+                        operator: Operator::Contains,
+                        value: "sponsored".to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Submitting to the wrong category is rejected
+        let request = Request::builder()
+            .method("PUT")
+            .uri("/api/rules/category/5")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&rule_set).unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let api_response: ApiResponse<String> = serde_json::from_slice(&body).unwrap();
+        assert!(!api_response.success);
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri("/api/rules/category/4")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&rule_set).unwrap()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let api_response: ApiResponse<String> = serde_json::from_slice(&body).unwrap();
+        assert!(api_response.success);
+
+        let saved = RuleSet::load_from_file(temp_dir.path().join("category_4.toml")).unwrap();
+        assert_eq!(saved.category_id, Some(4));
+    }
 }
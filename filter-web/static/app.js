@@ -12,12 +12,31 @@ async function loadStats() {
       document.getElementById("totalRuleSets").textContent = stats.total_rule_sets;
       document.getElementById("enabledRuleSets").textContent = stats.enabled_rule_sets;
       document.getElementById("totalRules").textContent = stats.total_rules;
+      displayRuleMatches(stats.rule_matches || []);
     }
   } catch (error) {
     console.error("Failed to load stats:", error);
   }
 }
 
+// Show the rules that matched most often since the filter started
+function displayRuleMatches(ruleMatches) {
+  const section = document.getElementById("ruleMatchesSection");
+  const list = document.getElementById("ruleMatches");
+
+  section.style.display = ruleMatches.length > 0 ? "block" : "none";
+  list.innerHTML = ruleMatches
+    .slice(0, 10)
+    .map(
+      (count) => `
+      <div class="rule-match" title="${escapeHtml(count.description || "")}">
+        <span class="rule-match-label">${escapeHtml(count.rule)}</span>
+        <span class="rule-match-count">${count.matches}</span>
+      </div>`,
+    )
+    .join("");
+}
+
 // Extract domain from URL
 function extractDomain(url) {
   try {
@@ -53,15 +72,22 @@ async function loadCombinedData() {
     const feeds = feedsResponse.data || [];
     const ruleSets = ruleSetsResponse.success ? ruleSetsResponse.data || [] : [];
 
-    // Create a map of rule sets by feed ID for quick lookup
+    // Create maps of rule sets by feed and category ID for quick lookup
     const ruleSetMap = {};
+    const categoryRuleSetMap = {};
     ruleSets.forEach((rs) => {
-      ruleSetMap[rs.feed_id] = rs;
+      if (rs.feed_id != null) {
+        ruleSetMap[rs.feed_id] = rs;
+      } else if (rs.category_id != null) {
+        categoryRuleSetMap[rs.category_id] = rs;
+      }
+      // Global rule sets are not tied to a single feed or category
     });
 
     // Store data globally for filtering
     window.allFeeds = feeds;
     window.ruleSetMap = ruleSetMap;
+    window.categoryRuleSetMap = categoryRuleSetMap;
 
     renderCombinedList();
   } catch (error) {
@@ -72,51 +98,129 @@ async function loadCombinedData() {
   }
 }
 
+// Describe a rule set for list items
+function describeRuleSet(ruleSet) {
+  return ruleSet
+    ? `${ruleSet.rules.length} rules | ${ruleSet.enabled ? "Enabled" : "Disabled"}`
+    : "No rules";
+}
+
 // Render the combined list based on current filter
 function renderCombinedList() {
   const combinedList = document.getElementById("combinedList");
   const filterWithRules = document.getElementById("filterWithRules").checked;
+  const groupByCategory = document.getElementById("groupByCategory").checked;
 
   combinedList.innerHTML = "";
 
-  // Filter feeds based on toggle
-  const feedsToShow = filterWithRules
-    ? window.allFeeds.filter((feed) => window.ruleSetMap[feed.id])
-    : window.allFeeds;
-
-  feedsToShow.forEach((feed) => {
-    const ruleSet = window.ruleSetMap[feed.id];
-    const feedItem = document.createElement("div");
-    feedItem.className = `feed-item ${ruleSet ? "has-rules" : "no-rules"}`;
-
-    const domain = extractDomain(feed.site_url);
-    const ruleInfo = ruleSet
-      ? `${ruleSet.rules.length} rules | ${ruleSet.enabled ? "Enabled" : "Disabled"}`
-      : "No rules";
-
-    feedItem.innerHTML = `
-      <div class="feed-info">
-        <h3>${escapeHtml(feed.title)}</h3>
-        <p>${domain} | ${ruleInfo}</p>
-      </div>
-      <div>
-        ${
-          ruleSet
-            ? `<button class="button button-secondary" onclick="editRules(${feed.id})">Edit Rules</button>
-               <button class="button button-danger" onclick="deleteRules(${feed.id})">Delete Rules</button>`
-            : `<button class="button button-primary" onclick="createRules(${feed.id}, '${escapeHtml(
-                feed.title
-              )}')">Create Rules</button>`
-        }
-      </div>
-    `;
+  if (groupByCategory) {
+    renderCategoryView(combinedList, filterWithRules);
+  } else {
+    // Filter feeds based on toggle
+    const feedsToShow = filterWithRules
+      ? window.allFeeds.filter((feed) => window.ruleSetMap[feed.id])
+      : window.allFeeds;
 
-    combinedList.appendChild(feedItem);
-  });
+    feedsToShow.forEach((feed) => {
+      combinedList.appendChild(createFeedItem(feed));
+    });
+  }
 
   combinedList.style.display = "block";
 }
 
+// Render feeds grouped under their Miniflux category
+function renderCategoryView(combinedList, filterWithRules) {
+  const categories = new Map();
+  window.allFeeds.forEach((feed) => {
+    const key = feed.category_id != null ? feed.category_id : "none";
+    if (!categories.has(key)) {
+      categories.set(key, {
+        id: feed.category_id,
+        title: feed.category_title || "Uncategorized",
+        feeds: [],
+      });
+    }
+    categories.get(key).feeds.push(feed);
+  });
+
+  const sorted = [...categories.values()].sort((a, b) => a.title.localeCompare(b.title));
+
+  sorted.forEach((category) => {
+    const ruleSet = category.id != null ? window.categoryRuleSetMap[category.id] : null;
+    const feeds = filterWithRules
+      ? category.feeds.filter((feed) => window.ruleSetMap[feed.id])
+      : category.feeds;
+
+    if (filterWithRules && !ruleSet && feeds.length === 0) {
+      return;
+    }
+
+    combinedList.appendChild(createCategoryItem(category, ruleSet));
+
+    const feedsContainer = document.createElement("div");
+    feedsContainer.className = "category-feeds";
+    feeds.forEach((feed) => {
+      feedsContainer.appendChild(createFeedItem(feed));
+    });
+    combinedList.appendChild(feedsContainer);
+  });
+}
+
+// Create the list item for a category
+function createCategoryItem(category, ruleSet) {
+  const categoryItem = document.createElement("div");
+  categoryItem.className = `category-item ${ruleSet ? "has-rules" : "no-rules"}`;
+
+  let buttons = "";
+  if (category.id != null) {
+    buttons = ruleSet
+      ? `<button class="button button-secondary" onclick="editCategoryRules(${category.id})">Edit Category Rules</button>
+         <button class="button button-danger" onclick="deleteCategoryRules(${category.id})">Delete Category Rules</button>
+         <button class="button button-primary" onclick="executeFilterForCategory(${category.id})">Execute Filter</button>`
+      : `<button class="button button-primary" onclick="createCategoryRules(${category.id})">Create Category Rules</button>`;
+  }
+
+  categoryItem.innerHTML = `
+    <div class="feed-info">
+      <h3>${escapeHtml(category.title)}</h3>
+      <p>${category.feeds.length} feeds | ${describeRuleSet(ruleSet)}</p>
+    </div>
+    <div>${buttons}</div>
+  `;
+
+  return categoryItem;
+}
+
+// Create the list item for a feed
+function createFeedItem(feed) {
+  const ruleSet = window.ruleSetMap[feed.id];
+  const feedItem = document.createElement("div");
+  feedItem.className = `feed-item ${ruleSet ? "has-rules" : "no-rules"}`;
+
+  const domain = extractDomain(feed.site_url);
+  const ruleInfo = describeRuleSet(ruleSet);
+
+  feedItem.innerHTML = `
+    <div class="feed-info">
+      <h3>${escapeHtml(feed.title)}</h3>
+      <p>${domain} | ${ruleInfo}</p>
+    </div>
+    <div>
+      ${
+        ruleSet
+          ? `<button class="button button-secondary" onclick="editRules(${feed.id})">Edit Rules</button>
+             <button class="button button-danger" onclick="deleteRules(${feed.id})">Delete Rules</button>`
+          : `<button class="button button-primary" onclick="createRules(${feed.id}, '${escapeHtml(
+              feed.title
+            )}')">Create Rules</button>`
+      }
+    </div>
+  `;
+
+  return feedItem;
+}
+
 // Actions
 async function createRules(feedId, feedName) {
   // Navigate directly to the edit page for this feed with 'new' parameter
@@ -147,6 +251,34 @@ function editRules(feedId) {
   window.location.href = `edit.html?feed=${feedId}`;
 }
 
+function createCategoryRules(categoryId) {
+  window.location.href = `edit.html?category=${categoryId}&new=true`;
+}
+
+function editCategoryRules(categoryId) {
+  window.location.href = `edit.html?category=${categoryId}`;
+}
+
+async function deleteCategoryRules(categoryId) {
+  if (!confirm("Are you sure you want to delete this category's rule set?")) {
+    return;
+  }
+
+  try {
+    const response = await deleteCategoryRuleSet(categoryId);
+
+    if (response.success) {
+      alert("Rule set deleted successfully!");
+      loadCombinedData();
+      loadStats();
+    } else {
+      alert("Failed to delete rule set: " + response.error);
+    }
+  } catch (error) {
+    alert("Failed to delete rule set: " + error.message);
+  }
+}
+
 function escapeHtml(text) {
   const div = document.createElement("div");
   div.textContent = text;
@@ -158,8 +290,9 @@ document.addEventListener("DOMContentLoaded", function () {
   loadStats();
   loadCombinedData();
 
-  // Add event listener for filter toggle
+  // Add event listeners for filter and grouping toggles
   document.getElementById("filterWithRules").addEventListener("change", renderCombinedList);
+  document.getElementById("groupByCategory").addEventListener("change", renderCombinedList);
 });
 
 // Load and display logs
@@ -281,6 +414,24 @@ async function executeFilterForFeed(feedId) {
   }
 }
 
+// Execute filter for a specific category
+async function executeFilterForCategory(categoryId) {
+  try {
+    const response = await executeCategoryFilter(categoryId);
+
+    if (response.success) {
+      const result = response.data;
+      alert(`Filter executed successfully!\n${result.message}`);
+      // Refresh logs to show the execution results
+      loadLogs();
+    } else {
+      alert("Failed to execute filter: " + response.error);
+    }
+  } catch (error) {
+    alert("Failed to execute filter: " + error.message);
+  }
+}
+
 // Update the renderCombinedList function to include execute buttons
 const originalRenderCombinedList = renderCombinedList;
 renderCombinedList = function () {
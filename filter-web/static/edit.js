@@ -5,30 +5,47 @@
 
 // Global variables
 let currentRuleSet = null;
-let currentFeedId = null;
+// What the edited rule set applies to: {type: "feed" | "category", id}
+let currentScope = null;
 let isNewRuleSet = false;
+// Action of the score threshold when it is not one the editor offers, kept
+// as loaded unless another action is picked
+let customScoreAction = null;
 
 // Initialize the page
 document.addEventListener("DOMContentLoaded", function () {
   const urlParams = new URLSearchParams(window.location.search);
   const feedId = urlParams.get("feed");
+  const categoryId = urlParams.get("category");
   const isNew = urlParams.get("new") === "true";
 
-  if (!feedId) {
-    showError("No feed ID provided");
+  if (feedId) {
+    currentScope = {type: "feed", id: parseInt(feedId)};
+  } else if (categoryId) {
+    currentScope = {type: "category", id: parseInt(categoryId)};
+  } else {
+    showError("No feed or category ID provided");
     return;
   }
 
-  currentFeedId = parseInt(feedId);
-
   if (isNew) {
     // Create a new rule set in memory without saving to server
-    createNewRuleSet(currentFeedId);
+    createNewRuleSet(currentScope);
   } else {
-    loadRuleSet(currentFeedId);
+    loadRuleSet(currentScope);
   }
 });
 
+// API path of the rule set for a scope
+function ruleSetPath(scope) {
+  return scope.type === "category" ? `/rules/category/${scope.id}` : `/rules/${scope.id}`;
+}
+
+// Rule set fields identifying a scope
+function scopeFields(scope) {
+  return scope.type === "category" ? {category_id: scope.id} : {feed_id: scope.id};
+}
+
 // API functions
 async function fetchAPI(endpoint) {
   const response = await fetch(`/api${endpoint}`);
@@ -54,7 +71,7 @@ async function postAPI(endpoint, data) {
 }
 
 // Create a new rule set in memory
-function createNewRuleSet(feedId) {
+function createNewRuleSet(scope) {
   const loadingEl = document.getElementById("loading");
   const errorEl = document.getElementById("error");
   const editorEl = document.getElementById("editor");
@@ -64,7 +81,7 @@ function createNewRuleSet(feedId) {
 
   // Create a default rule set structure
   currentRuleSet = {
-    feed_id: feedId,
+    ...scopeFields(scope),
     enabled: true,
     rules: [],
   };
@@ -75,9 +92,9 @@ function createNewRuleSet(feedId) {
 }
 
 // Load rule set
-async function loadRuleSet(feedId) {
+async function loadRuleSet(scope) {
   try {
-    const response = await fetchAPI(`/rules/${feedId}`);
+    const response = await fetchAPI(ruleSetPath(scope));
 
     const loadingEl = document.getElementById("loading");
     const errorEl = document.getElementById("error");
@@ -126,24 +143,46 @@ function populateEditor(ruleSet) {
     throw new Error("Required DOM elements not found");
   }
 
-  feedTitle.textContent = `Feed ${ruleSet.feed_id}`;
+  if (currentScope.type === "category") {
+    feedTitle.textContent = `Category ${currentScope.id}`;
 
-  // Try to get the actual feed name from Miniflux API
-  fetchAPI(`/feeds/${ruleSet.feed_id}`)
-    .then((response) => {
-      if (response.success && response.data) {
-        feedTitle.textContent = `${response.data.title}`;
-      }
-    })
-    .catch(() => {
-      // Keep the fallback if API fails
-    });
+    // Try to get the actual category name from the feeds in it
+    fetchAPI("/feeds")
+      .then((response) => {
+        const feed = response.success && response.data.find((f) => f.category_id === currentScope.id);
+        if (feed) {
+          feedTitle.textContent = `${feed.category_title} (category)`;
+        }
+      })
+      .catch(() => {
+        // Keep the fallback if API fails
+      });
+  } else {
+    feedTitle.textContent = `Feed ${currentScope.id}`;
+
+    // Try to get the actual feed name from Miniflux API
+    fetchAPI(`/feeds/${currentScope.id}`)
+      .then((response) => {
+        if (response.success && response.data) {
+          feedTitle.textContent = `${response.data.title}`;
+        }
+      })
+      .catch(() => {
+        // Keep the fallback if API fails
+      });
+  }
   enabledCheckbox.checked = ruleSet.enabled !== false;
+  document.getElementById("fetchContent").checked = ruleSet.fetch_content === true;
+  document.getElementById("timezone").value = ruleSet.timezone || "";
+  setSchedule(document.getElementById("ruleSetSchedule"), ruleSet);
+  setScoring(ruleSet.scoring);
+  document.getElementById("ruleSetExpired").hidden = ruleSet.expired !== true;
   container.innerHTML = "";
 
   if (ruleSet.rules && ruleSet.rules.length > 0) {
-    ruleSet.rules.forEach((rule) => {
-      addRuleToContainer(rule);
+    const expiredRules = ruleSet.expired_rules || [];
+    ruleSet.rules.forEach((rule, index) => {
+      addRuleToContainer(rule, expiredRules.includes(index));
     });
   } else {
     showEmptyState();
@@ -162,7 +201,7 @@ function showEmptyState() {
 }
 
 // Add rule to container
-function addRuleToContainer(rule = null) {
+function addRuleToContainer(rule = null, expired = false) {
   const container = document.getElementById("rulesContainer");
 
   // Remove empty state if present
@@ -178,8 +217,19 @@ function addRuleToContainer(rule = null) {
   const conditionsContainer = ruleElement.querySelector(".conditions-container");
 
   if (rule) {
-    rule.conditions.forEach((condition) => {
-      addConditionToRule(conditionsContainer, condition);
+    ruleCard.querySelector(".rule-name").value = rule.name || "";
+    ruleCard.querySelector(".rule-description").value = rule.description || "";
+    ruleCard.querySelector(".rule-id").value = rule.id || "";
+    setSchedule(ruleCard.querySelector(".schedule"), rule);
+    ruleCard.classList.toggle("rule-expired", expired);
+    ruleCard.querySelector(".expired-badge").hidden = !expired;
+    setRuleAction(ruleCard, rule.action || "markread");
+    ruleCard.querySelector(".rule-priority").value = rule.priority || "";
+    ruleCard.querySelector(".rule-stop").checked = rule.stop === true;
+    ruleCard.querySelector(".rule-score").value = rule.score ?? "";
+    updateScoreMode(ruleCard.querySelector(".rule-score"));
+    rule.conditions.forEach((node) => {
+      addNodeToContainer(conditionsContainer, node);
     });
   } else {
     // New rule gets one empty condition
@@ -189,6 +239,130 @@ function addRuleToContainer(rule = null) {
   container.appendChild(ruleElement);
 }
 
+// Fill the schedule inputs from a rule or rule set
+function setSchedule(scheduleElement, source) {
+  scheduleElement.querySelectorAll("[data-schedule]").forEach((input) => {
+    input.value = source[input.dataset.schedule] || "";
+  });
+}
+
+// Read the schedule inputs, leaving out empty ones
+function getSchedule(scheduleElement) {
+  const schedule = {};
+  scheduleElement.querySelectorAll("[data-schedule]").forEach((input) => {
+    const value = input.value.trim();
+    if (value) {
+      schedule[input.dataset.schedule] = value;
+    }
+  });
+  return schedule;
+}
+
+// Fill the score threshold and its action
+function setScoring(scoring) {
+  const select = document.getElementById("scoreAction");
+  select.querySelector("[data-custom]")?.remove();
+  document.getElementById("scoreThreshold").value = scoring ? scoring.threshold : "";
+
+  const action = scoring ? scoring.action : "markread";
+  customScoreAction = null;
+  if (Array.from(select.options).some((option) => option.value === action)) {
+    select.value = action;
+  } else {
+    customScoreAction = action;
+    const option = new Option("Custom (from rule file)", "custom");
+    option.dataset.custom = "";
+    select.add(option);
+    select.value = "custom";
+  }
+}
+
+// Read the score threshold, leaving scoring out when there is none
+function getScoring() {
+  const threshold = document.getElementById("scoreThreshold").value.trim();
+  if (threshold === "") {
+    return {};
+  }
+  const select = document.getElementById("scoreAction");
+  const action = select.value === "custom" ? customScoreAction : select.value;
+  return {scoring: {threshold: Number(threshold), action}};
+}
+
+// Scored rules add to the entry's score instead of acting
+function updateScoreMode(scoreInput) {
+  const ruleCard = scoreInput.closest(".rule-card");
+  ruleCard.querySelector(".rule-action").disabled = scoreInput.value !== "";
+}
+
+// Show the option inputs belonging to the selected action
+function updateActionOptions(select) {
+  const ruleCard = select.closest(".rule-card");
+  ruleCard.querySelectorAll(".action-options").forEach((options) => {
+    options.hidden = !options.dataset.for.split(" ").includes(select.value);
+  });
+}
+
+// Option inputs of the selected action, if it has any
+function actionOptionsFor(ruleCard, type) {
+  return Array.from(ruleCard.querySelectorAll(".action-options")).find((options) =>
+    options.dataset.for.split(" ").includes(type),
+  );
+}
+
+// Fill the action controls. Simple actions are plain strings, actions with
+// options are objects like { rewritetitle: { pattern, replacement } }
+function setRuleAction(ruleCard, action) {
+  const select = ruleCard.querySelector(".rule-action");
+  if (typeof action === "string") {
+    select.value = action;
+  } else {
+    const [type, options] = Object.entries(action)[0];
+    select.value = type;
+    actionOptionsFor(ruleCard, type)
+      .querySelectorAll("[data-option]")
+      .forEach((input) => {
+        input.value = options[input.dataset.option] ?? "";
+      });
+  }
+  updateActionOptions(select);
+}
+
+// Read the action controls back into the rule file format. Empty optional
+// inputs are left out so the server applies its defaults.
+function getRuleAction(ruleCard) {
+  const type = ruleCard.querySelector(".rule-action").value;
+  const optionsElement = actionOptionsFor(ruleCard, type);
+  if (!optionsElement) {
+    return type;
+  }
+
+  const options = {};
+  optionsElement.querySelectorAll("[data-option]").forEach((input) => {
+    if (input.value === "" && input.dataset.optional !== undefined) {
+      return;
+    }
+    options[input.dataset.option] = input.type === "number" ? Number(input.value) : input.value;
+  });
+  return { [type]: options };
+}
+
+// Add a condition tree node (single condition or group) to a container
+function addNodeToContainer(container, node) {
+  if (node.all) {
+    addGroupToContainer(container, "all", node.all);
+  } else if (node.any) {
+    addGroupToContainer(container, "any", node.any);
+  } else if (node.of) {
+    addGroupToContainer(container, "atleast", node.of, node.at_least);
+  } else if (node.not) {
+    // "None of" groups are saved as not(any(...)), unwrap them again
+    const children = node.not.any ? node.not.any : [node.not];
+    addGroupToContainer(container, "not", children);
+  } else {
+    addConditionToRule(container, node);
+  }
+}
+
 // Add condition to rule
 function addConditionToRule(container, condition = null) {
   const template = document.getElementById("conditionTemplate");
@@ -200,13 +374,144 @@ function addConditionToRule(container, condition = null) {
     const valueInput = conditionElement.querySelector(".condition-value");
 
     fieldSelect.value = condition.field;
+    updateOperatorOptions(fieldSelect);
     operatorSelect.value = condition.operator;
     valueInput.value = condition.value;
+    conditionElement.querySelector(".condition-case").checked =
+      condition.case_sensitive ?? isCaseSensitiveByDefault(condition.operator);
+    conditionElement.querySelector(".condition-accents").checked = condition.ignore_accents === true;
+    conditionElement.querySelector(".condition-distance").value = condition.distance ?? "";
+    conditionElement.querySelector(".condition-selector").value = condition.selector ?? "";
+    conditionElement.querySelector(".condition-attribute").value = condition.attribute ?? "";
+    updateDistanceOption(operatorSelect);
+  } else {
+    updateOperatorOptions(conditionElement.querySelector(".condition-field"));
   }
 
   container.appendChild(conditionElement);
 }
 
+// Regexes are case-sensitive unless a condition says otherwise, the other
+// operators ignore case
+function isCaseSensitiveByDefault(operator) {
+  return operator === "matches";
+}
+
+// Only offer the operators that work on the kind of the selected field
+function updateOperatorOptions(fieldSelect) {
+  const kind = fieldSelect.selectedOptions[0].dataset.kind;
+  const operatorSelect = fieldSelect.parentElement.querySelector(".condition-operator");
+
+  Array.from(operatorSelect.options).forEach((option) => {
+    option.hidden = !option.dataset.kinds.split(" ").includes(kind);
+  });
+  fieldSelect.parentElement.querySelectorAll(".condition-option").forEach((option) => {
+    option.hidden = !option.dataset.kinds.split(" ").includes(kind);
+  });
+  // Selectors only narrow down the content
+  fieldSelect.parentElement.querySelectorAll(".condition-selector, .condition-attribute").forEach((input) => {
+    input.hidden = fieldSelect.value !== "content";
+  });
+  if (operatorSelect.selectedOptions[0].hidden) {
+    operatorSelect.value = Array.from(operatorSelect.options).find((option) => !option.hidden).value;
+  }
+  updateDistanceOption(operatorSelect);
+}
+
+// The word distance only applies to the near operator
+function updateDistanceOption(operatorSelect) {
+  operatorSelect.parentElement.querySelector(".condition-distance").hidden = operatorSelect.value !== "near";
+}
+
+// Add condition group to a container
+function addGroupToContainer(container, type = "any", children = null, atLeast = 2) {
+  const template = document.getElementById("groupTemplate");
+  const groupElement = template.content.cloneNode(true);
+  const childContainer = groupElement.querySelector(".conditions-container");
+  const typeSelect = groupElement.querySelector(".group-type");
+
+  typeSelect.value = type;
+  groupElement.querySelector(".group-threshold").value = atLeast;
+  updateGroupType(typeSelect);
+
+  if (children && children.length > 0) {
+    children.forEach((node) => {
+      addNodeToContainer(childContainer, node);
+    });
+  } else {
+    // New group gets one empty condition
+    addConditionToRule(childContainer);
+  }
+
+  container.appendChild(groupElement);
+}
+
+// Show the threshold input of "at least" groups
+function updateGroupType(typeSelect) {
+  const threshold = typeSelect.parentElement.querySelector(".group-threshold");
+  threshold.hidden = typeSelect.value !== "atleast";
+}
+
+// Collect the condition tree nodes directly inside a container
+function collectConditions(container) {
+  const nodes = [];
+
+  container.querySelectorAll(":scope > .condition-row, :scope > .condition-group").forEach((el) => {
+    if (el.classList.contains("condition-group")) {
+      const type = el.querySelector(".group-type").value;
+      const children = collectConditions(el.querySelector(".conditions-container"));
+
+      // Groups without any filled in condition are dropped
+      if (children.length === 0) {
+        return;
+      }
+
+      if (type === "not") {
+        nodes.push({not: children.length === 1 ? children[0] : {any: children}});
+      } else if (type === "atleast") {
+        const atLeast = Number(el.querySelector(".group-threshold").value);
+        nodes.push({at_least: atLeast, of: children});
+      } else {
+        nodes.push({[type]: children});
+      }
+      return;
+    }
+
+    const field = el.querySelector(".condition-field").value;
+    const operator = el.querySelector(".condition-operator").value;
+    const value = el.querySelector(".condition-value").value.trim();
+
+    if (value) {
+      const condition = {field, operator, value};
+      const caseOption = el.querySelector(".condition-case");
+      const accentsOption = el.querySelector(".condition-accents");
+
+      // Only store the options when they apply and differ from the defaults
+      if (!caseOption.parentElement.hidden && caseOption.checked !== isCaseSensitiveByDefault(operator)) {
+        condition.case_sensitive = caseOption.checked;
+      }
+      if (!accentsOption.parentElement.hidden && accentsOption.checked) {
+        condition.ignore_accents = true;
+      }
+      const distance = el.querySelector(".condition-distance");
+      if (!distance.hidden && distance.value !== "") {
+        condition.distance = Number(distance.value);
+      }
+      const selector = el.querySelector(".condition-selector");
+      const attribute = el.querySelector(".condition-attribute");
+      if (!selector.hidden && selector.value.trim()) {
+        condition.selector = selector.value.trim();
+        if (attribute.value.trim()) {
+          condition.attribute = attribute.value.trim();
+        }
+      }
+      nodes.push(condition);
+    }
+  });
+
+  return nodes;
+}
+
 // Event handlers
 function addRule() {
   addRuleToContainer();
@@ -223,14 +528,22 @@ function removeRule(button) {
   }
 }
 
+// Find the conditions container belonging to a rule card or group button
+function conditionsContainerFor(button) {
+  const owner = button.closest(".condition-group, .rule-card");
+  return owner.querySelector(".conditions-container");
+}
+
 function addCondition(button) {
-  const ruleCard = button.closest(".rule-card");
-  const conditionsContainer = ruleCard.querySelector(".conditions-container");
-  addConditionToRule(conditionsContainer);
+  addConditionToRule(conditionsContainerFor(button));
+}
+
+function addGroup(button) {
+  addGroupToContainer(conditionsContainerFor(button));
 }
 
 function removeCondition(button) {
-  const conditionRow = button.closest(".condition-row");
+  const conditionRow = button.closest(".condition-row, .condition-group");
   const conditionsContainer = conditionRow.parentElement;
 
   conditionRow.remove();
@@ -241,51 +554,94 @@ function removeCondition(button) {
   }
 }
 
-// Save rules
-async function saveRules() {
-  try {
-    const enabled = document.getElementById("enabled").checked;
-    const rules = [];
-    const validationErrors = [];
-
-    // Collect and validate all rules
-    document.querySelectorAll(".rule-card").forEach((ruleCard, ruleIndex) => {
-      const conditions = [];
-      ruleCard.querySelectorAll(".condition-row").forEach((conditionRow) => {
-        const field = conditionRow.querySelector(".condition-field").value;
-        const operator = conditionRow.querySelector(".condition-operator").value;
-        const value = conditionRow.querySelector(".condition-value").value.trim();
-
-        if (value) {
-          conditions.push({field, operator, value});
-        }
-      });
+// Read the editor into a rule set, or show what needs fixing and return null
+function collectRuleSet() {
+  const enabled = document.getElementById("enabled").checked;
+  const fetchContent = document.getElementById("fetchContent").checked;
+  const timezone = document.getElementById("timezone").value.trim();
+  const rules = [];
+  const validationErrors = [];
+
+  // Collect and validate all rules
+  document.querySelectorAll(".rule-card").forEach((ruleCard, ruleIndex) => {
+    const conditions = collectConditions(ruleCard.querySelector(".conditions-container"));
+
+    // Validation: check for rules without conditions
+    if (conditions.length === 0) {
+      validationErrors.push(`Rule ${ruleIndex + 1} has no valid conditions`);
+      return;
+    }
 
-      // Validation: check for rules without conditions
-      if (conditions.length === 0) {
-        validationErrors.push(`Rule ${ruleIndex + 1} has no valid conditions`);
-        return;
-      }
+    const action = getRuleAction(ruleCard);
+    const actionOptions = typeof action === "object" ? Object.values(action)[0] : {};
+    if (actionOptions.pattern === "") {
+      validationErrors.push(`Rule ${ruleIndex + 1} needs a rewrite pattern`);
+      return;
+    }
+    if (actionOptions.url === "") {
+      validationErrors.push(`Rule ${ruleIndex + 1} needs a webhook URL`);
+      return;
+    }
 
-      rules.push({
-        action: "markread",
-        conditions,
-      });
+    const id = ruleCard.querySelector(".rule-id").value.trim();
+    const name = ruleCard.querySelector(".rule-name").value.trim();
+    const description = ruleCard.querySelector(".rule-description").value.trim();
+    const priority = Number(ruleCard.querySelector(".rule-priority").value);
+    const stop = ruleCard.querySelector(".rule-stop").checked;
+    if (!Number.isInteger(priority)) {
+      validationErrors.push(`Rule ${ruleIndex + 1} needs a whole number as priority`);
+      return;
+    }
+    const scoreValue = ruleCard.querySelector(".rule-score").value;
+    const score = Number(scoreValue);
+    if (!Number.isInteger(score)) {
+      validationErrors.push(`Rule ${ruleIndex + 1} needs a whole number as score`);
+      return;
+    }
+
+    rules.push({
+      ...(id && { id }),
+      ...(name && { name }),
+      ...(description && { description }),
+      ...(scoreValue === "" && { action }),
+      ...(priority && { priority }),
+      ...(scoreValue !== "" && { score }),
+      ...(stop && { stop }),
+      ...getSchedule(ruleCard.querySelector(".schedule")),
+      conditions,
     });
+  });
+
+  const scoring = getScoring();
+  if (scoring.scoring && !Number.isInteger(scoring.scoring.threshold)) {
+    validationErrors.push("The score threshold needs to be a whole number");
+  }
+
+  // If there are validation errors, show them and don't go on
+  if (validationErrors.length > 0) {
+    alert("Please fix the following errors first:\n\n" + validationErrors.join("\n"));
+    return null;
+  }
 
-    // If there are validation errors, show them and don't save
-    if (validationErrors.length > 0) {
-      alert("Please fix the following errors before saving:\n\n" + validationErrors.join("\n"));
+  return {
+    ...scopeFields(currentScope),
+    enabled,
+    fetch_content: fetchContent,
+    ...(timezone && { timezone }),
+    ...getSchedule(document.getElementById("ruleSetSchedule")),
+    ...scoring,
+    rules,
+  };
+}
+
+// Save rules
+async function saveRules() {
+  try {
+    const ruleSet = collectRuleSet();
+    if (!ruleSet) {
       return;
     }
 
-    // Prepare rule set
-    const ruleSet = {
-      feed_id: currentFeedId,
-      enabled,
-      rules,
-    };
-
     // Save to server - use POST for new rule sets, PUT for existing ones
     let response;
     if (isNewRuleSet) {
@@ -294,10 +650,12 @@ async function saveRules() {
         isNewRuleSet = false; // Mark as no longer new after successful save
       }
     } else {
-      response = await putAPI(`/rules/${currentFeedId}`, ruleSet);
+      response = await putAPI(ruleSetPath(currentScope), ruleSet);
     }
 
     if (response.success) {
+      // Show the IDs the server assigned to new rules
+      await loadRuleSet(currentScope);
       alert("Rules saved successfully!");
       // Stay on the edit page - no redirect
     } else {
@@ -308,10 +666,70 @@ async function saveRules() {
   }
 }
 
+// Show what the rules in the editor would do to the unread entries, without
+// saving them or applying any action
+async function previewRules(button) {
+  const ruleSet = collectRuleSet();
+  if (!ruleSet) {
+    return;
+  }
+
+  button.textContent = "Previewing...";
+  button.disabled = true;
+  try {
+    const response = await postAPI("/preview", ruleSet);
+    if (response.success) {
+      displayPreview(response.data);
+    } else {
+      alert("Failed to preview rules: " + response.error);
+    }
+  } catch (error) {
+    alert("Failed to preview rules: " + error.message);
+  } finally {
+    button.textContent = "Preview";
+    button.disabled = false;
+  }
+}
+
+function displayPreview(entries) {
+  const results = document.getElementById("previewResults");
+  document.getElementById("previewSection").hidden = false;
+
+  if (entries.length === 0) {
+    results.innerHTML = `<p class="preview-note">No unread entries.</p>`;
+    return;
+  }
+
+  results.innerHTML = entries
+    .map(
+      (entry) => `
+      <div class="preview-entry${entry.matches.length > 0 ? " preview-matched" : ""}">
+        <div class="preview-title">${escapeHtml(entry.title)}</div>
+        <div class="preview-matches">
+          ${entry.matches.length > 0 ? escapeHtml(entry.matches.join(", ")) : "No rule applies"}
+        </div>
+        ${entry.score ? formatScore(entry.score) : ""}
+      </div>`,
+    )
+    .join("");
+}
+
+// Score total against the threshold, with the rules that added to it
+function formatScore(score) {
+  const breakdown = score.breakdown
+    .map((rule) => `${rule.name || rule.rule} ${rule.score > 0 ? "+" : ""}${rule.score}`)
+    .join(", ");
+  const reached = score.total >= score.threshold;
+  return `
+        <div class="preview-score${reached ? " score-reached" : ""}">
+          Score ${score.total} / ${score.threshold}${breakdown && `: ${escapeHtml(breakdown)}`}
+        </div>`;
+}
+
 // Execute filter now
 async function executeNow(buttonElement) {
-  if (!currentFeedId) {
-    alert("No feed ID available");
+  if (!currentScope) {
+    alert("No feed or category ID available");
     return;
   }
 
@@ -329,7 +747,11 @@ async function executeNow(buttonElement) {
     button.textContent = "Executing...";
     button.disabled = true;
 
-    const response = await fetch(`/api/execute/${currentFeedId}`, {
+    const executePath =
+      currentScope.type === "category"
+        ? `/api/execute/category/${currentScope.id}`
+        : `/api/execute/${currentScope.id}`;
+    const response = await fetch(executePath, {
       method: "POST",
       headers: {"Content-Type": "application/json"},
     });
@@ -131,6 +131,27 @@ async function deleteRuleSet(feedId) {
   }
 }
 
+/**
+ * Delete a category rule set
+ * @param {number} categoryId - The category ID
+ * @returns {Promise<{success: boolean, data?: Object, error?: string}>}
+ */
+async function deleteCategoryRuleSet(categoryId) {
+  try {
+    const response = await fetch(`/api/rules/category/${categoryId}`, {
+      method: "DELETE",
+    });
+
+    const result = await response.json();
+    return result;
+  } catch (error) {
+    return {
+      success: false,
+      error: error.message,
+    };
+  }
+}
+
 /**
  * Fetch logs from the API
  * @returns {Promise<{success: boolean, data?: Array, error?: string}>}
@@ -228,3 +249,24 @@ async function executeFilter(feedId) {
     };
   }
 }
+
+/**
+ * Execute filter for every feed in a category
+ * @param {number} categoryId - The category ID
+ * @returns {Promise<{success: boolean, data?: Object, error?: string}>}
+ */
+async function executeCategoryFilter(categoryId) {
+  try {
+    const response = await fetch(`/api/execute/category/${categoryId}`, {
+      method: "POST",
+    });
+
+    const result = await response.json();
+    return result;
+  } catch (error) {
+    return {
+      success: false,
+      error: error.message,
+    };
+  }
+}
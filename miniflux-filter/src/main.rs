@@ -90,7 +90,14 @@ async fn main() -> Result<()> {
 
         // Run both web server and filtering engine concurrently
         try_join!(
-            start_web_server(rules_dir, web_client, config.web_port, Some(log_collector)),
+            start_web_server(
+                rules_dir,
+                web_client,
+                config.web_port,
+                Some(log_collector),
+                filter_engine.history(),
+                filter_engine.rule_stats()
+            ),
             filter_engine.run()
         )?;
     } else {